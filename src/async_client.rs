@@ -0,0 +1,348 @@
+#![warn(missing_docs)]
+//! An async client for requesting data from the MetroHero API.
+//!
+//! Requires the `async` feature. Requests are built and their responses interpreted exactly as they
+//! are by the blocking [`MetroHeroClient`](crate::MetroHeroClient).
+//!
+//! API Documentation: <https://dcmetrohero.com/apis>
+use std::sync::Arc;
+
+use reqwest::Client;
+use serde::de::DeserializeOwned;
+
+use crate::cache::ResponseCache;
+use crate::client::{
+    api_key_from_env, check_response, parse_body, Endpoint, MetroHeroClientBuilder,
+};
+use crate::errors::MetroHeroError;
+use crate::ratelimit::{Quota, RateLimiter};
+use crate::retry::RetryPolicy;
+use crate::schemas::{
+    StationCode, StationReports, StationTags, SystemMetricsResponse, TrainPrediction,
+    TrainPredictions, TrainReports, TrainTags, TripInfo, Tweet,
+};
+
+/// An async client for requesting data from the MetroHero API.
+///
+/// # API Keys
+/// When instantiated using [`AsyncMetroHeroClient::default()`], the client will attempt to fetch an
+/// API key from the environment at `METROHERO_API_KEY`. To set a key explicitely, use
+/// [`AsyncMetroHeroClient::new()`]
+///
+/// # Example
+///
+/// ```no_run
+/// # async fn run() {
+/// use metrohero_rs::AsyncMetroHeroClient;
+/// let client = AsyncMetroHeroClient::default();
+/// let system_metrics = client.get_system_metrics().await.unwrap();
+/// # }
+/// ```
+pub struct AsyncMetroHeroClient {
+    pub(crate) http_client: Client,
+    pub(crate) api_url_base: String,
+    pub(crate) rate_limiter: Option<Arc<RateLimiter>>,
+    pub(crate) retry_policy: Option<RetryPolicy>,
+    pub(crate) cache: Option<Arc<ResponseCache>>,
+}
+
+impl AsyncMetroHeroClient {
+    /// Instantiate a new async client for interacting with the MetroHero API.
+    ///
+    /// To configure timeouts, proxies, or the API's base URL, use
+    /// [`MetroHeroClientBuilder::build_async()`].
+    pub fn new(api_key: String) -> Self {
+        MetroHeroClientBuilder::new(api_key)
+            .build_async()
+            .expect("Failed to initialize HTTP client")
+    }
+
+    /// Get the requests which may still be made before the client's rate limiter throttles them.
+    ///
+    /// Returns `None` if the client was not built with a rate limiter.
+    pub fn remaining_quota(&self) -> Option<Quota> {
+        self.rate_limiter
+            .as_ref()
+            .map(|rate_limiter| rate_limiter.remaining())
+    }
+
+    /// Get the client's response cache, if it was built with one.
+    pub fn cache(&self) -> Option<&ResponseCache> {
+        self.cache.as_deref()
+    }
+
+    /// Send a request to the MetroHero API, retrying failures allowed by the retry policy.
+    async fn send_request<T: DeserializeOwned>(
+        &self,
+        endpoint: Endpoint,
+    ) -> Result<T, MetroHeroError> {
+        let request_path = endpoint.path();
+        if let Some(body) = self.cache().and_then(|cache| cache.get(&request_path)) {
+            return parse_body(&request_path, &body);
+        }
+
+        let mut attempt = 1;
+        let body = loop {
+            match self.fetch(&endpoint, &request_path).await {
+                Ok(body) => break body,
+                Err(error) => {
+                    let delay = self
+                        .retry_policy
+                        .as_ref()
+                        .and_then(|policy| policy.next_delay(&request_path, attempt, &error));
+                    match delay {
+                        Some(delay) => tokio::time::sleep(delay).await,
+                        None => return Err(error),
+                    }
+                    attempt += 1;
+                }
+            }
+        };
+
+        let model = parse_body(&request_path, &body)?;
+        if let Some(cache) = self.cache() {
+            cache.insert(&request_path, endpoint.family(), body);
+        }
+        Ok(model)
+    }
+
+    /// Make a single attempt at a request, returning the body of a successful response.
+    async fn fetch(
+        &self,
+        endpoint: &Endpoint,
+        request_path: &str,
+    ) -> Result<String, MetroHeroError> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            tokio::time::sleep(rate_limiter.reserve()?).await;
+        }
+
+        let request_url = format!("{}{}", self.api_url_base, request_path);
+        let response = self
+            .http_client
+            .get(request_url)
+            .send()
+            .await
+            .map_err(|e| MetroHeroError::http(request_path, e))?;
+        let status = response.status().as_u16();
+        let body = response
+            .text()
+            .await
+            .map_err(|e| MetroHeroError::http(request_path, e))?;
+        check_response(endpoint, status, &body)?;
+        Ok(body)
+    }
+
+    /// Gets real-time system-wide metrics, broken down by line and direction of travel.
+    ///
+    /// See [`MetroHeroClient::get_system_metrics`](crate::MetroHeroClient::get_system_metrics).
+    pub async fn get_system_metrics(&self) -> Result<SystemMetricsResponse, MetroHeroError> {
+        self.send_request(Endpoint::SystemMetrics).await
+    }
+
+    /// Gets real-time trip information given current conditions.
+    ///
+    /// See [`MetroHeroClient::get_trip_info`](crate::MetroHeroClient::get_trip_info).
+    pub async fn get_trip_info(
+        &self,
+        from_station_code: &StationCode,
+        to_station_code: &StationCode,
+    ) -> Result<TripInfo, MetroHeroError> {
+        self.send_request(Endpoint::TripInfo(*from_station_code, *to_station_code))
+            .await
+    }
+
+    /// Gets the last 30 minutes' worth of Metrorail-related tweets from Twitter.
+    ///
+    /// See [`MetroHeroClient::get_tweets`](crate::MetroHeroClient::get_tweets).
+    pub async fn get_tweets(&self) -> Result<Vec<Tweet>, MetroHeroError> {
+        self.send_request(Endpoint::Tweets).await
+    }
+
+    /// Gets real-time train predictions for the entire Metrorail system.
+    ///
+    /// See [`MetroHeroClient::get_train_positions`](crate::MetroHeroClient::get_train_positions).
+    pub async fn get_train_positions(&self) -> Result<Vec<TrainPrediction>, MetroHeroError> {
+        self.send_request(Endpoint::TrainPositions).await
+    }
+
+    /// Gets real-time rider reports, referred to as tags, for all trains.
+    ///
+    /// See [`MetroHeroClient::get_train_reports`](crate::MetroHeroClient::get_train_reports).
+    pub async fn get_train_reports(&self) -> Result<TrainReports, MetroHeroError> {
+        self.send_request(Endpoint::TrainReports).await
+    }
+
+    /// Gets real-time rider reports about a particular train, referred to as tags.
+    ///
+    /// See [`MetroHeroClient::get_train_report`](crate::MetroHeroClient::get_train_report).
+    pub async fn get_train_report(&self, train_id: String) -> Result<TrainTags, MetroHeroError> {
+        self.send_request(Endpoint::TrainReport(train_id)).await
+    }
+
+    /// Gets real-time and scheduled train predictions for all stations.
+    ///
+    /// See [`MetroHeroClient::get_train_predictions`](crate::MetroHeroClient::get_train_predictions).
+    pub async fn get_train_predictions(&self) -> Result<TrainPredictions, MetroHeroError> {
+        self.send_request(Endpoint::TrainPredictions).await
+    }
+
+    /// Gets real-time and scheduled train predictions for a particular station.
+    ///
+    /// See [`MetroHeroClient::get_station_train_predictions`](crate::MetroHeroClient::get_station_train_predictions).
+    pub async fn get_station_train_predictions(
+        &self,
+        station_code: &StationCode,
+    ) -> Result<Vec<TrainPrediction>, MetroHeroError> {
+        self.send_request(Endpoint::StationTrainPredictions(*station_code))
+            .await
+    }
+
+    /// Gets real-time rider reports, referred to as tags, for all stations.
+    ///
+    /// See [`MetroHeroClient::get_station_reports`](crate::MetroHeroClient::get_station_reports).
+    pub async fn get_station_reports(&self) -> Result<StationReports, MetroHeroError> {
+        self.send_request(Endpoint::StationReports).await
+    }
+
+    /// Gets real-time rider reports about a particular station, referred to as tags.
+    ///
+    /// See [`MetroHeroClient::get_station_report`](crate::MetroHeroClient::get_station_report).
+    pub async fn get_station_report(
+        &self,
+        station_code: &StationCode,
+    ) -> Result<StationTags, MetroHeroError> {
+        self.send_request(Endpoint::StationReport(*station_code))
+            .await
+    }
+}
+
+impl Default for AsyncMetroHeroClient {
+    /// Instantiate an AsyncMetroHeroClient using an API key from the `METROHERO_API_KEY` environment
+    /// variable.
+    fn default() -> Self {
+        AsyncMetroHeroClient::new(api_key_from_env())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::async_client::AsyncMetroHeroClient;
+    use crate::client::MetroHeroClientBuilder;
+    use crate::errors::MetroHeroError;
+    use crate::schemas::tests::get_test_data;
+    use crate::schemas::StationCode;
+    use mockito::mock;
+
+    /// Get a client which sends requests to the mockito server.
+    fn test_client() -> AsyncMetroHeroClient {
+        MetroHeroClientBuilder::new(String::from("METROHERO_API_KEY"))
+            .base_url(mockito::server_url())
+            .https_only(false)
+            .build_async()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_tripinfo() {
+        let mock = mock("GET", "/metrorail/trips/K01/K02")
+            .with_status(200)
+            .with_body(get_test_data(String::from("tripinfo_api.json")))
+            .create();
+        let client = test_client();
+        client
+            .get_trip_info(&StationCode::K01, &StationCode::K02)
+            .await
+            .unwrap();
+        mock.assert()
+    }
+
+    #[tokio::test]
+    async fn test_get_tripinfo_invalid() {
+        let mock = mock("GET", "/metrorail/trips/UNKNOWN/UNKNOWN")
+            .with_status(400)
+            .create();
+
+        let client = test_client();
+        let error = client
+            .get_trip_info(&StationCode::UNKNOWN, &StationCode::UNKNOWN)
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error,
+            MetroHeroError::InvalidItinerary {
+                request_path: Some(String::from("/metrorail/trips/UNKNOWN/UNKNOWN"))
+            }
+        );
+        mock.assert()
+    }
+
+    #[tokio::test]
+    async fn test_get_system_metrics() {
+        let mock = mock("GET", "/metrorail/metrics")
+            .with_status(200)
+            .with_body(get_test_data(String::from("system_metrics_api.json")))
+            .create();
+
+        let client = test_client();
+        client.get_system_metrics().await.unwrap();
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_get_station_train_predictions() {
+        let mock = mock(
+            "GET",
+            "/metrorail/stations/K01/trains?includeScheduledPredictions=true",
+        )
+        .with_status(200)
+        .with_body(get_test_data(String::from(
+            "station_strain_prediction_docs.json",
+        )))
+        .create();
+
+        let client = test_client();
+        client
+            .get_station_train_predictions(&StationCode::K01)
+            .await
+            .unwrap();
+        mock.assert()
+    }
+
+    #[tokio::test]
+    async fn test_get_train_report_invalid() {
+        let mock = mock("GET", "/metrorail/trains/123/tags")
+            .with_status(400)
+            .create();
+        let client = test_client();
+        let error = client
+            .get_train_report(String::from("123"))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error,
+            MetroHeroError::InvalidTrainId {
+                request_path: Some(String::from("/metrorail/trains/123/tags"))
+            }
+        );
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn test_unexpected_status() {
+        let mock = mock("GET", "/metrorail/tweets")
+            .with_status(404)
+            .with_body("Not Found")
+            .create();
+        let client = test_client();
+        let error = client.get_tweets().await.unwrap_err();
+        assert_eq!(
+            error,
+            MetroHeroError::UnexpectedStatus {
+                request_path: Some(String::from("/metrorail/tweets")),
+                status: 404,
+                body: String::from("Not Found")
+            }
+        );
+        mock.assert();
+    }
+}
@@ -0,0 +1,235 @@
+#![warn(missing_docs)]
+//! An in-memory cache of MetroHero API responses, to avoid spending quota on repeated requests.
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Groups of API endpoints whose data is refreshed at similar rates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndpointFamily {
+    /// Train predictions, train positions and trip information.
+    Predictions,
+    /// System-wide line metrics.
+    Metrics,
+    /// Rider reports (tags) about trains and stations.
+    Tags,
+    /// Metrorail-related tweets.
+    Tweets,
+}
+
+/// Cache hit and miss counts for a [`ResponseCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    /// Requests served from the cache.
+    pub hits: u64,
+    /// Requests which were not cached, or whose cached response had expired.
+    pub misses: u64,
+    /// Responses currently held by the cache, including expired ones not yet evicted.
+    pub entries: usize,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    family: EndpointFamily,
+    body: String,
+    expires_at: Instant,
+}
+
+/// An in-memory cache of raw API responses, keyed by request path.
+///
+/// Each [`EndpointFamily`] has its own time-to-live. A TTL of zero disables caching for that
+/// family.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use metrohero_rs::MetroHeroClient;
+/// use metrohero_rs::cache::{EndpointFamily, ResponseCache};
+///
+/// let cache = ResponseCache::new().ttl(EndpointFamily::Predictions, Duration::from_secs(5));
+/// let client = MetroHeroClient::builder("YOUR_API_KEY".to_string())
+///     .cache(cache)
+///     .build()
+///     .unwrap();
+/// assert_eq!(client.cache().unwrap().stats().hits, 0);
+/// ```
+#[derive(Debug)]
+pub struct ResponseCache {
+    ttls: HashMap<EndpointFamily, Duration>,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ResponseCache {
+    /// Create a cache with default TTLs: 10 seconds for predictions, 2 minutes for metrics,
+    /// 5 minutes for tags, and 1 minute for tweets.
+    pub fn new() -> Self {
+        let ttls = HashMap::from([
+            (EndpointFamily::Predictions, Duration::from_secs(10)),
+            (EndpointFamily::Metrics, Duration::from_secs(2 * 60)),
+            (EndpointFamily::Tags, Duration::from_secs(5 * 60)),
+            (EndpointFamily::Tweets, Duration::from_secs(60)),
+        ]);
+        Self {
+            ttls,
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Set how long responses from a family of endpoints are cached.
+    pub fn ttl(mut self, family: EndpointFamily, ttl: Duration) -> Self {
+        self.ttls.insert(family, ttl);
+        self
+    }
+
+    /// Get the time-to-live for a family of endpoints.
+    pub fn ttl_for(&self, family: EndpointFamily) -> Duration {
+        self.ttls.get(&family).copied().unwrap_or_default()
+    }
+
+    /// Get an unexpired response body for a request path, recording a hit or miss.
+    pub fn get(&self, request_path: &str) -> Option<String> {
+        let entries = self.entries.lock().unwrap();
+        let body = entries
+            .get(request_path)
+            .filter(|entry| Instant::now() < entry.expires_at)
+            .map(|entry| entry.body.clone());
+
+        match body {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        body
+    }
+
+    /// Cache the response body for a request path, if its family is cached.
+    pub fn insert(&self, request_path: &str, family: EndpointFamily, body: String) {
+        let ttl = self.ttl_for(family);
+        if ttl.is_zero() {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap();
+        let now = Instant::now();
+        entries.retain(|_, entry| now < entry.expires_at);
+        entries.insert(
+            request_path.to_string(),
+            CacheEntry {
+                family,
+                body,
+                expires_at: now + ttl,
+            },
+        );
+    }
+
+    /// Remove the cached response for a request path, e.g. `/metrorail/stations/K04/tags`.
+    pub fn invalidate(&self, request_path: &str) {
+        self.entries.lock().unwrap().remove(request_path);
+    }
+
+    /// Remove all cached responses from a family of endpoints.
+    pub fn invalidate_family(&self, family: EndpointFamily) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|_, entry| entry.family != family);
+    }
+
+    /// Remove all cached responses.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Get hit and miss counts for this cache.
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.entries.lock().unwrap().len(),
+        }
+    }
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        ResponseCache::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::{CacheStats, EndpointFamily, ResponseCache};
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_hits_and_misses() {
+        let cache = ResponseCache::new();
+        assert_eq!(cache.get("/metrorail/metrics"), None);
+
+        cache.insert("/metrorail/metrics", EndpointFamily::Metrics, "{}".into());
+        assert_eq!(cache.get("/metrorail/metrics"), Some(String::from("{}")));
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                entries: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_entries_expire() {
+        let cache = ResponseCache::new().ttl(EndpointFamily::Predictions, Duration::from_millis(1));
+        cache.insert(
+            "/metrorail/trains",
+            EndpointFamily::Predictions,
+            "[]".into(),
+        );
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(cache.get("/metrorail/trains"), None);
+    }
+
+    #[test]
+    fn test_zero_ttl_disables_caching() {
+        let cache = ResponseCache::new().ttl(EndpointFamily::Tweets, Duration::ZERO);
+        cache.insert("/metrorail/tweets", EndpointFamily::Tweets, "[]".into());
+        assert_eq!(cache.stats().entries, 0);
+    }
+
+    #[test]
+    fn test_invalidation() {
+        let cache = ResponseCache::new();
+        cache.insert(
+            "/metrorail/stations/K04/tags",
+            EndpointFamily::Tags,
+            "{}".into(),
+        );
+        cache.insert(
+            "/metrorail/stations/K05/tags",
+            EndpointFamily::Tags,
+            "{}".into(),
+        );
+        cache.insert(
+            "/metrorail/trains",
+            EndpointFamily::Predictions,
+            "[]".into(),
+        );
+
+        cache.invalidate("/metrorail/stations/K04/tags");
+        assert_eq!(cache.get("/metrorail/stations/K04/tags"), None);
+        assert_eq!(cache.stats().entries, 2);
+
+        cache.invalidate_family(EndpointFamily::Tags);
+        assert_eq!(cache.stats().entries, 1);
+
+        cache.clear();
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
@@ -1,13 +1,32 @@
 //! A CLI for interacting with the MetroHero API.
+use std::cmp::Ordering;
+use std::io::{self, IsTerminal};
 use std::process;
 use std::str::FromStr;
 
+use clap::Args;
 use clap::Parser;
-use clap::{arg, Subcommand};
+use clap::Subcommand;
+use serde::Serialize;
+use strum::IntoEnumIterator;
 
 use crate::errors::MetroHeroError;
-use crate::schemas::StationCode;
-use crate::{client, format};
+use crate::network::{self, Direction};
+use crate::output::{
+    AlertRecord, CsvRecord, DepartureRecord, LineMetricsRecord, NearbyStationRecord, OutageRecord,
+    OutputFormat, ServiceGapRecord, StationRecord, TrainDetailRecord, TrainDetails, TrainRecord,
+    TripRecord, TweetRecord,
+};
+use crate::schemas::{
+    ElevatorEscalatorOutage, LineCode, LineMetrics, StationCode, TrainPrediction, TripInfo,
+};
+use crate::search::StationMatch;
+use crate::stations::StationInfo;
+use crate::{client, format, output, planner};
+
+/// The environment variable naming a directory of recorded fixtures to serve API responses from.
+#[cfg(feature = "test-replay")]
+const REPLAY_DIR_ENV_VAR: &str = "METROHERO_REPLAY_DIR";
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -16,6 +35,16 @@ pub struct Cli {
     command: Commands,
     #[arg(long, help = "MetroHero API key")]
     api_key: Option<String>,
+    #[arg(long, help = "MetroHero API base URL, e.g. for a caching proxy")]
+    api_url: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = OutputFormat::Table,
+        help = "Output format"
+    )]
+    output: OutputFormat,
 }
 
 #[derive(Subcommand)]
@@ -34,8 +63,117 @@ pub enum Commands {
         station: String,
     },
 
-    /// Print a table of station names and their RTU codes.
+    /// Get elevator and escalator outages at a Metrorail station.
+    Outages {
+        #[arg()]
+        station: String,
+    },
+
+    /// List recent tweets about Metrorail, newest first.
+    Tweets {
+        #[command(flatten)]
+        filter: FeedFilter,
+    },
+
+    /// List current WMATA alerts on a line or at a station, newest first.
+    Alerts {
+        #[command(flatten)]
+        filter: FeedFilter,
+    },
+
+    /// List the positions of trains across the system.
+    Trains {
+        #[arg(long, help = "Only show trains on this line, e.g. RD or Red")]
+        line: Option<LineCode>,
+        #[arg(
+            long,
+            value_parser = clap::value_parser!(i64).range(1..=2),
+            help = "Only show trains travelling in this direction number, 1 or 2"
+        )]
+        direction: Option<i64>,
+        #[arg(long, help = "Sort the most delayed trains first")]
+        by_delay: bool,
+    },
+
+    /// Get the position of a train and rider reports about it.
+    Train {
+        #[arg()]
+        train_id: String,
+    },
+
+    /// Print a table of stations, their RTU codes, lines and facilities.
     Stations {},
+
+    /// Find the stations nearest to a location.
+    Near {
+        #[arg(allow_negative_numbers = true)]
+        latitude: f64,
+        #[arg(allow_negative_numbers = true)]
+        longitude: f64,
+        #[arg(
+            short = 'n',
+            long,
+            default_value_t = 5,
+            help = "Number of stations to list"
+        )]
+        count: usize,
+        #[arg(long, help = "Also show departures from the nearest station")]
+        departures: bool,
+    },
+
+    /// Get system-wide health metrics for each line.
+    Metrics {
+        #[arg(long, help = "Only show metrics for this line, e.g. RD or Red")]
+        line: Option<LineCode>,
+    },
+
+    /// List current service gaps, most severe first.
+    Gaps {
+        #[arg(
+            long,
+            default_value_t = 0.0,
+            help = "Hide gaps less than this many minutes longer than scheduled"
+        )]
+        threshold: f64,
+    },
+}
+
+/// Options for narrowing down tweets and alerts.
+#[derive(Args)]
+pub struct FeedFilter {
+    #[arg(long, help = "Only show items about this line, e.g. RD or Red")]
+    line: Option<LineCode>,
+    #[arg(long, help = "Only show items about this station")]
+    station: Option<String>,
+    #[arg(long, help = "Only show items with this keyword or text")]
+    keyword: Option<String>,
+}
+
+impl FeedFilter {
+    /// Whether an item about these stations and lines, with these keywords and text, passes the
+    /// filter. `station` is the filter's station, already parsed from the user's input.
+    fn matches(
+        &self,
+        station: Option<StationCode>,
+        station_codes: &[StationCode],
+        line_codes: &[LineCode],
+        keywords: &[String],
+        text: &str,
+    ) -> bool {
+        let line_matches = self.line.is_none_or(|line| line_codes.contains(&line));
+        // Riders asking about one platform of a station complex want to hear about the other.
+        let station_matches = station.is_none_or(|station| {
+            station_codes.contains(&station)
+                || network::other_platform(station)
+                    .is_some_and(|other| station_codes.contains(&other))
+        });
+        let keyword_matches = self.keyword.as_ref().is_none_or(|keyword| {
+            let keyword = keyword.to_lowercase();
+            keywords.iter().any(|k| k.to_lowercase() == keyword)
+                || text.to_lowercase().contains(&keyword)
+        });
+        line_matches && station_matches && keyword_matches
+    }
 }
 
 fn parse_user_station_input(input: &str) -> StationCode {
@@ -45,58 +183,454 @@ fn parse_user_station_input(input: &str) -> StationCode {
     //     Next, see if it is an exact match for a station name.
     let name_attempt = StationCode::from_name(input);
 
-    if let Ok(..) = code_attempt {
-        code_attempt.unwrap()
-    } else if let Ok(..) = name_attempt {
-        name_attempt.unwrap()
-    } else {
-        eprintln!("{}", MetroHeroError::InvalidStation);
+    if let Ok(code) = code_attempt {
+        return code;
+    } else if let Ok(code) = name_attempt {
+        return code;
+    }
+
+    //     Finally, search for stations with similar names.
+    let matches = StationCode::search(input);
+    let confident: Vec<&StationMatch> = matches.iter().filter(|m| m.is_confident()).collect();
+    match confident[..] {
+        [station_match] => station_match.station,
+        [_, _, ..] if io::stdin().is_terminal() => prompt_for_station(input, &confident),
+        _ => {
+            eprintln!("{}", MetroHeroError::InvalidStation { request_path: None });
+            if !matches.is_empty() {
+                let suggestions: Vec<String> = matches
+                    .iter()
+                    .take(3)
+                    .map(|m| format!("{} ({})", m.station.to_name(), m.station))
+                    .collect();
+                eprintln!("Did you mean {}?", suggestions.join(", or "));
+            }
+            process::exit(1);
+        }
+    }
+}
+
+/// Ask the user which of several similarly named stations they meant.
+fn prompt_for_station(input: &str, matches: &[&StationMatch]) -> StationCode {
+    eprintln!("\"{input}\" matches several stations:");
+    for (i, station_match) in matches.iter().enumerate() {
+        let station = station_match.station;
+        eprintln!("  {}. {} ({station})", i + 1, station.to_name());
+    }
+    eprint!("Choose a station [1-{}]: ", matches.len());
+
+    let mut choice = String::new();
+    let chosen = io::stdin()
+        .read_line(&mut choice)
+        .ok()
+        .and_then(|_| choice.trim().parse::<usize>().ok())
+        .and_then(|i| matches.get(i.wrapping_sub(1)));
+    match chosen {
+        Some(station_match) => station_match.station,
+        None => {
+            eprintln!("{}", MetroHeroError::InvalidStation { request_path: None });
+            process::exit(1);
+        }
+    }
+}
+
+/// Get information about a trip from a station to a neighboring one.
+///
+/// Outages and alerts are only reported by the API as part of trip information, so this is the
+/// cheapest request which reports them for a station.
+fn get_station_trip_info(client: &client::MetroHeroClient, station: StationCode) -> TripInfo {
+    let neighbor = network::lines_serving(station)
+        .into_iter()
+        .flat_map(|line| {
+            [Direction::D1, Direction::D2]
+                .map(|direction| network::next_station(station, line, direction))
+        })
+        .flatten()
+        .next();
+    let Some(neighbor) = neighbor else {
+        eprintln!("{}", MetroHeroError::InvalidStation { request_path: None });
+        process::exit(1);
+    };
+
+    match client.get_trip_info(&station, &neighbor) {
+        Ok(trip_info) => trip_info,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Build an API client from the user's CLI options.
+fn get_client(cli: &Cli) -> client::MetroHeroClient {
+    let api_key = match &cli.api_key {
+        Some(api_key) => api_key.clone(),
+        _ => client::api_key_from_env(),
+    };
+
+    let mut builder = client::MetroHeroClient::builder(api_key);
+    if let Some(api_url) = &cli.api_url {
+        // Custom endpoints, such as local mocks or caching proxies, may not use TLS.
+        builder = builder.base_url(api_url).https_only(false);
+    }
+    // Serve responses from recorded fixtures, for the CLI integration tests.
+    #[cfg(feature = "test-replay")]
+    if let Some(dir) = std::env::var_os(REPLAY_DIR_ENV_VAR) {
+        match crate::transport::ReplayTransport::open(dir) {
+            Ok(transport) => builder = builder.transport(transport),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    match builder.build() {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Order timestamps reported by the API newest first.
+///
+/// Without the `chrono` feature the timestamps can't be parsed, so they are compared as strings.
+fn newest_first(timestamp: &str, other: &str) -> Ordering {
+    #[cfg(feature = "chrono")]
+    return crate::datetime::cmp_timestamps(other, timestamp);
+    #[cfg(not(feature = "chrono"))]
+    other.cmp(timestamp)
+}
+
+/// Print items in the machine-readable format chosen by the user, flattening them into records
+/// with `to_record` for CSV.
+fn print_items<T: Serialize, R: CsvRecord>(
+    output_format: OutputFormat,
+    items: &[T],
+    to_record: impl Fn(&T) -> R,
+) {
+    if let Err(e) = output::write_items(io::stdout().lock(), output_format, items, to_record) {
+        eprintln!("{}", e);
         process::exit(1);
     }
 }
 
+/// Print departures and rider reports for a station.
+///
+/// Departures from every platform in `platform_codes`, e.g. both levels of Metro Center, are
+/// merged and listed under the first.
+fn print_station_departures(
+    client: &client::MetroHeroClient,
+    platform_codes: &[StationCode],
+    output_format: OutputFormat,
+) {
+    let station_code = &platform_codes[0];
+    let mut departures = vec![];
+    for platform_code in platform_codes {
+        match client.get_station_train_predictions(platform_code) {
+            Ok(platform_departures) => departures.extend(platform_departures),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+    if output_format != OutputFormat::Table {
+        departures.sort_by(TrainPrediction::cmp_departure);
+        print_items(output_format, &departures, |train| {
+            DepartureRecord::from(train)
+        });
+        return;
+    }
+    let station_tags = client.get_station_report(station_code).unwrap();
+    format::print_departures(station_code, departures, station_tags);
+}
+
 pub fn run_cli() {
     let cli = Cli::parse();
+    let output_format = cli.output;
 
-    let client = match cli.api_key {
-        Some(api_key) => client::MetroHeroClient::new(api_key),
-        _ => client::MetroHeroClient::default(),
-    };
-
-    match cli.command {
+    match &cli.command {
         Commands::Departures {
             station: station_input,
         } => {
-            let station_code = parse_user_station_input(&station_input);
-            let departures = match client.get_station_train_predictions(&station_code) {
-                Ok(departures) => departures,
-                Err(e) => {
-                    eprintln!("{}", e);
-                    process::exit(1);
-                }
-            };
-            let station_tags = client.get_station_report(&station_code).unwrap();
-            format::print_departures(&station_code, departures, station_tags);
+            let client = get_client(&cli);
+            let station_code = parse_user_station_input(station_input);
+            print_station_departures(&client, &[station_code], output_format);
         }
 
         Commands::Plan {
             start_station: start_station_input,
             end_station: end_station_input,
         } => {
-            let start_station = parse_user_station_input(&start_station_input);
-            let end_station = parse_user_station_input(&end_station_input);
+            let client = get_client(&cli);
+            let start_station = parse_user_station_input(start_station_input);
+            let end_station = parse_user_station_input(end_station_input);
             let plan = client.get_trip_info(&start_station, &end_station);
             match plan {
+                Ok(plan) if output_format != OutputFormat::Table => {
+                    print_items(output_format, &[plan], |plan| TripRecord::from(plan));
+                }
                 Ok(plan) => format::print_plan(plan),
+                // Fall back to a route planned offline, e.g. if the API is down. Scripts expect
+                // trip information, so only do so for tables.
+                Err(e) => match planner::plan_route(start_station, end_station) {
+                    Ok(route) if output_format == OutputFormat::Table => {
+                        eprintln!("{}", e);
+                        format::print_route(&route);
+                    }
+                    _ => {
+                        eprintln!("{}", e);
+                        process::exit(1);
+                    }
+                },
+            }
+        }
+
+        Commands::Outages {
+            station: station_input,
+        } => {
+            let client = get_client(&cli);
+            let station_code = parse_user_station_input(station_input);
+
+            // Outages are only reported as part of trip information, so use the outages at the
+            // origin of a trip from the station.
+            let trip_info = get_station_trip_info(&client, station_code);
+            if output_format == OutputFormat::Table {
+                format::print_outages(
+                    &station_code,
+                    &trip_info.from_station_elevator_outages,
+                    &trip_info.from_station_escalator_outages,
+                );
+            } else {
+                let outages: Vec<&ElevatorEscalatorOutage> = trip_info
+                    .from_station_elevator_outages
+                    .iter()
+                    .chain(&trip_info.from_station_escalator_outages)
+                    .flatten()
+                    .collect();
+                print_items(output_format, &outages, |outage| {
+                    OutageRecord::from(*outage)
+                });
+            }
+        }
+
+        Commands::Tweets { filter } => {
+            let client = get_client(&cli);
+            let station = filter.station.as_deref().map(parse_user_station_input);
+            let mut tweets = match client.get_tweets() {
+                Ok(tweets) => tweets,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            };
+            tweets.retain(|tweet| {
+                filter.matches(
+                    station,
+                    &tweet.station_codes,
+                    &tweet.line_codes,
+                    &tweet.keywords,
+                    &tweet.text,
+                )
+            });
+            tweets.sort_by(|a, b| newest_first(&a.date, &b.date));
+
+            if output_format == OutputFormat::Table {
+                format::print_tweets(tweets);
+            } else {
+                print_items(output_format, &tweets, |tweet| TweetRecord::from(tweet));
+            }
+        }
+
+        Commands::Alerts { filter } => {
+            let client = get_client(&cli);
+            let station = filter.station.as_deref().map(parse_user_station_input);
+
+            // Alerts are only reported as part of trip information, for the stations on the trip,
+            // so request a single trip along the line or from the station.
+            let trip_info = match (filter.line, station) {
+                (Some(line), _) => {
+                    let (Some(from), Some(to)) = (
+                        network::terminal(line, Direction::D2),
+                        network::terminal(line, Direction::D1),
+                    ) else {
+                        eprintln!("No stations are known for line {line}");
+                        process::exit(1);
+                    };
+                    match client.get_trip_info(&from, &to) {
+                        Ok(trip_info) => trip_info,
+                        Err(e) => {
+                            eprintln!("{}", e);
+                            process::exit(1);
+                        }
+                    }
+                }
+                (None, Some(station)) => get_station_trip_info(&client, station),
+                (None, None) => {
+                    eprintln!("Alerts are reported by line or station; use --line or --station");
+                    process::exit(1);
+                }
+            };
+            let mut alerts = trip_info.metro_alerts.unwrap_or_default();
+
+            alerts.retain(|alert| {
+                filter.matches(
+                    station,
+                    &alert.station_codes,
+                    &alert.line_codes,
+                    &alert.keywords,
+                    &alert.description,
+                )
+            });
+            alerts.sort_by(|a, b| newest_first(&a.date, &b.date));
+
+            if output_format == OutputFormat::Table {
+                format::print_alerts(alerts);
+            } else {
+                print_items(output_format, &alerts, |alert| AlertRecord::from(alert));
+            }
+        }
+
+        Commands::Trains {
+            line,
+            direction,
+            by_delay,
+        } => {
+            let client = get_client(&cli);
+            let mut trains = match client.get_train_positions() {
+                Ok(trains) => trains,
                 Err(e) => {
                     eprintln!("{}", e);
                     process::exit(1);
                 }
+            };
+            trains.retain(|train| {
+                line.is_none_or(|line| train.line == line)
+                    && direction.is_none_or(|direction| train.direction_number == direction)
+            });
+
+            if output_format == OutputFormat::Table {
+                format::print_trains(trains, *by_delay);
+            } else {
+                format::sort_trains(&mut trains, *by_delay);
+                print_items(output_format, &trains, |train| TrainRecord::from(train));
+            }
+        }
+
+        Commands::Train { train_id } => {
+            let client = get_client(&cli);
+            let train_tags = match client.get_train_report(train_id.clone()) {
+                Ok(train_tags) => train_tags,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            };
+            // Rider reports are still worth showing if the train's position is unavailable.
+            let position = match client.get_train_positions() {
+                Ok(trains) => trains.into_iter().find(|train| train.train_id == *train_id),
+                Err(e) => {
+                    eprintln!("{}", e);
+                    None
+                }
+            };
+
+            if output_format == OutputFormat::Table {
+                format::print_train(train_id, position.as_ref(), &train_tags);
+            } else {
+                let details = TrainDetails {
+                    train_id,
+                    position: position.as_ref(),
+                    tags: &train_tags,
+                };
+                print_items(output_format, &[details], |details| {
+                    TrainDetailRecord::from(details)
+                });
             }
         }
 
         Commands::Stations {} => {
-            format::print_stations();
+            if output_format == OutputFormat::Table {
+                format::print_stations();
+            } else {
+                let stations: Vec<&StationInfo> = StationCode::iter()
+                    .filter_map(|station| station.info())
+                    .collect();
+                print_items(output_format, &stations, |info| StationRecord::from(*info));
+            }
+        }
+
+        Commands::Metrics { line } => {
+            let client = get_client(&cli);
+            let metrics = match client.get_system_metrics() {
+                Ok(metrics) => metrics,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            };
+
+            if output_format == OutputFormat::Table {
+                format::print_metrics(&metrics, *line);
+            } else {
+                let line_metrics: Vec<&LineMetrics> = metrics
+                    .line_metrics_by_line
+                    .iter()
+                    .filter(|(metrics_line, _)| line.is_none_or(|line| line == *metrics_line))
+                    .map(|(_, line_metrics)| line_metrics)
+                    .collect();
+                print_items(output_format, &line_metrics, |line_metrics| {
+                    LineMetricsRecord::from(*line_metrics)
+                });
+            }
+        }
+
+        Commands::Gaps { threshold } => {
+            let client = get_client(&cli);
+            let metrics = match client.get_system_metrics() {
+                Ok(metrics) => metrics,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    process::exit(1);
+                }
+            };
+
+            if output_format == OutputFormat::Table {
+                format::print_gaps(&metrics, *threshold);
+            } else {
+                let gaps = format::service_gaps(&metrics, *threshold);
+                print_items(output_format, &gaps, |gap| ServiceGapRecord::from(*gap));
+            }
+        }
+
+        Commands::Near {
+            latitude,
+            longitude,
+            count,
+            departures,
+        } => {
+            let nearby = StationCode::nearest(*latitude, *longitude, *count);
+            if output_format != OutputFormat::Table {
+                // Departures have a different structure to stations, so can't share the output.
+                if *departures {
+                    eprintln!("--departures is only supported with table output");
+                    process::exit(1);
+                }
+                print_items(output_format, &nearby, |station| {
+                    NearbyStationRecord::from(station)
+                });
+                return;
+            }
+
+            format::print_nearby_stations(&nearby);
+            if let (true, Some(nearest)) = (departures, nearby.first()) {
+                let client = get_client(&cli);
+                println!();
+                print_station_departures(&client, &nearest.info.platform_codes(), output_format);
+            }
         }
     }
 }
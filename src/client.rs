@@ -2,19 +2,395 @@
 //! A client for requesting data from the MetroHero API.
 //!
 //! API Documentation: <https://dcmetrohero.com/apis>
-#[cfg(not(test))]
 use std::env;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use reqwest::blocking::Client;
 use reqwest::header::{HeaderMap, ACCEPT};
-use reqwest::tls;
+use reqwest::{tls, Proxy};
 use serde::de::DeserializeOwned;
 
+#[cfg(feature = "async")]
+use crate::async_client::AsyncMetroHeroClient;
+use crate::cache::{EndpointFamily, ResponseCache};
 use crate::errors::MetroHeroError;
+use crate::ratelimit::{Quota, RateLimiter};
+use crate::retry::RetryPolicy;
 use crate::schemas::{
     StationCode, StationReports, StationTags, SystemMetricsResponse, TrainPrediction,
     TrainPredictions, TrainReports, TrainTags, TripInfo, Tweet,
 };
+use crate::transport::{ReqwestTransport, Transport};
+
+/// The base URL of the MetroHero API.
+pub(crate) const API_URL_BASE: &str = "https://dcmetrohero.com/api/v1";
+
+/// The environment variable consulted for an API key by `default()` constructors.
+pub(crate) const API_KEY_ENV_VAR: &str = "METROHERO_API_KEY";
+
+/// A MetroHero API endpoint, along with any parameters it requires.
+///
+/// Shared between the blocking and async clients so both build identical requests and interpret
+/// failures the same way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Endpoint {
+    SystemMetrics,
+    TripInfo(StationCode, StationCode),
+    Tweets,
+    TrainPositions,
+    TrainReports,
+    TrainReport(String),
+    TrainPredictions,
+    StationTrainPredictions(StationCode),
+    StationReports,
+    StationReport(StationCode),
+}
+
+impl Endpoint {
+    /// The request path for this endpoint, relative to the API base URL.
+    pub(crate) fn path(&self) -> String {
+        match self {
+            Endpoint::SystemMetrics => String::from("/metrorail/metrics"),
+            Endpoint::TripInfo(from_station_code, to_station_code) => {
+                format!("/metrorail/trips/{from_station_code}/{to_station_code}")
+            }
+            Endpoint::Tweets => String::from("/metrorail/tweets"),
+            Endpoint::TrainPositions => String::from("/metrorail/trains"),
+            Endpoint::TrainReports => String::from("/metrorail/trains/tags"),
+            Endpoint::TrainReport(train_id) => {
+                // Train IDs come from users, so keep them from rewriting the rest of the path.
+                let train_id = utf8_percent_encode(train_id, NON_ALPHANUMERIC);
+                format!("/metrorail/trains/{train_id}/tags")
+            }
+            Endpoint::TrainPredictions => String::from("/metrorail/stations/trains"),
+            Endpoint::StationTrainPredictions(station_code) => format!(
+                "/metrorail/stations/{station_code}/trains?includeScheduledPredictions=true"
+            ),
+            Endpoint::StationReports => String::from("/metrorail/stations/tags"),
+            Endpoint::StationReport(station_code) => {
+                format!("/metrorail/stations/{station_code}/tags")
+            }
+        }
+    }
+
+    /// The family of endpoints this endpoint belongs to, for caching purposes.
+    pub(crate) fn family(&self) -> EndpointFamily {
+        match self {
+            Endpoint::SystemMetrics => EndpointFamily::Metrics,
+            Endpoint::Tweets => EndpointFamily::Tweets,
+            Endpoint::TrainReports
+            | Endpoint::TrainReport(..)
+            | Endpoint::StationReports
+            | Endpoint::StationReport(..) => EndpointFamily::Tags,
+            Endpoint::TripInfo(..)
+            | Endpoint::TrainPositions
+            | Endpoint::TrainPredictions
+            | Endpoint::StationTrainPredictions(..) => EndpointFamily::Predictions,
+        }
+    }
+
+    /// Translate a failed request into a more specific error for this endpoint, if possible.
+    pub(crate) fn map_error(&self, error: MetroHeroError) -> MetroHeroError {
+        match (self, error) {
+            // Invalid request here means one or more station codes were invalid.
+            (Endpoint::TripInfo(..), MetroHeroError::InvalidRequest { request_path }) => {
+                MetroHeroError::InvalidItinerary { request_path }
+            }
+            // Invalid request here can only mean the train ID was invalid.
+            (Endpoint::TrainReport(..), MetroHeroError::InvalidRequest { request_path }) => {
+                MetroHeroError::InvalidTrainId { request_path }
+            }
+            // 400 Errors here indicate that the station ID was invalid.
+            (
+                Endpoint::StationTrainPredictions(..) | Endpoint::StationReport(..),
+                MetroHeroError::InvalidRequest { request_path },
+            ) => MetroHeroError::InvalidStation { request_path },
+            (_, error) => error,
+        }
+    }
+}
+
+/// Maximum number of characters of a response body retained in [`MetroHeroError::UnexpectedStatus`].
+const MAX_ERROR_BODY_CHARS: usize = 256;
+
+/// Check the HTTP status code of a MetroHero API response, mapping failures to errors.
+pub(crate) fn check_status(
+    request_path: &str,
+    status: u16,
+    body: &str,
+) -> Result<(), MetroHeroError> {
+    let request_path = Some(request_path.to_string());
+    match status {
+        200..=299 => Ok(()),
+        400 => Err(MetroHeroError::InvalidRequest { request_path }),
+        401 => Err(MetroHeroError::AuthenticationError { request_path }),
+        429 | 503 => Err(MetroHeroError::RateLimited { request_path }),
+        _ => Err(MetroHeroError::UnexpectedStatus {
+            request_path,
+            status,
+            body: truncate_body(body),
+        }),
+    }
+}
+
+/// Truncate a response body so it can be carried in an error without bloating it.
+fn truncate_body(body: &str) -> String {
+    match body.char_indices().nth(MAX_ERROR_BODY_CHARS) {
+        Some((index, _)) => format!("{}...", &body[..index]),
+        None => body.to_string(),
+    }
+}
+
+/// Check a raw response from the MetroHero API, mapping failures to errors for its endpoint.
+pub(crate) fn check_response(
+    endpoint: &Endpoint,
+    status: u16,
+    body: &str,
+) -> Result<(), MetroHeroError> {
+    check_status(&endpoint.path(), status, body).map_err(|e| endpoint.map_error(e))
+}
+
+/// Parse the body of a successful response from the MetroHero API.
+pub(crate) fn parse_body<T: DeserializeOwned>(
+    request_path: &str,
+    body: &str,
+) -> Result<T, MetroHeroError> {
+    let deserializer = &mut serde_json::Deserializer::from_str(body);
+    let model: T = serde_path_to_error::deserialize(deserializer)
+        .map_err(|e| MetroHeroError::parse(request_path, e))?;
+    Ok(model)
+}
+
+/// Default headers sent with every request to the MetroHero API.
+pub(crate) fn default_headers(api_key: &str) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("apiKey", api_key.parse().unwrap());
+    headers.insert(ACCEPT, "application/json".parse().unwrap());
+    headers
+}
+
+/// Fetch an API key from the `METROHERO_API_KEY` environment variable.
+pub(crate) fn api_key_from_env() -> String {
+    env::var(API_KEY_ENV_VAR)
+        .unwrap_or_else(|_| panic!("Environment variable {API_KEY_ENV_VAR} is missing"))
+}
+
+/// TLS implementations which may be used to communicate with the MetroHero API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TlsBackend {
+    /// The default TLS implementation of the underlying HTTP client (usually native-tls).
+    Default,
+    /// Rustls. Requires the `rustls` feature.
+    #[cfg(feature = "rustls")]
+    Rustls,
+}
+
+impl Default for TlsBackend {
+    /// Rustls when the `rustls` feature is enabled, otherwise the HTTP client's default.
+    fn default() -> Self {
+        #[cfg(feature = "rustls")]
+        let backend = TlsBackend::Rustls;
+
+        #[cfg(not(feature = "rustls"))]
+        let backend = TlsBackend::Default;
+
+        backend
+    }
+}
+
+/// Apply the settings of a [`MetroHeroClientBuilder`] to a reqwest client builder.
+///
+/// This is a macro because the blocking and async reqwest builders share method names, but not a
+/// trait, and both clients must be configured identically.
+macro_rules! configure_http_client {
+    ($settings:expr, $http_builder:expr) => {{
+        let settings: &MetroHeroClientBuilder = $settings;
+        let mut http_builder = $http_builder
+            .default_headers(default_headers(&settings.api_key))
+            .https_only(settings.https_only)
+            .min_tls_version(tls::Version::TLS_1_2);
+
+        if let Some(connect_timeout) = settings.connect_timeout {
+            http_builder = http_builder.connect_timeout(connect_timeout);
+        }
+        if let Some(timeout) = settings.timeout {
+            http_builder = http_builder.timeout(timeout);
+        }
+        if let Some(proxy) = &settings.proxy {
+            http_builder = http_builder.proxy(Proxy::all(proxy.as_str())?);
+        }
+        if let Some(user_agent) = &settings.user_agent {
+            http_builder = http_builder.user_agent(user_agent.as_str());
+        }
+        match settings.tls_backend {
+            TlsBackend::Default => {}
+            #[cfg(feature = "rustls")]
+            TlsBackend::Rustls => http_builder = http_builder.use_rustls_tls(),
+        }
+        http_builder
+    }};
+}
+
+/// A builder for configuring a [`MetroHeroClient`] (or, with the `async` feature, an
+/// [`AsyncMetroHeroClient`](crate::AsyncMetroHeroClient)).
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use metrohero_rs::client::MetroHeroClientBuilder;
+///
+/// let client = MetroHeroClientBuilder::new("YOUR_API_KEY".to_string())
+///     .base_url("http://localhost:8080/api/v1")
+///     .https_only(false)
+///     .timeout(Duration::from_secs(10))
+///     .user_agent("my-metro-dashboard/1.0")
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct MetroHeroClientBuilder {
+    api_key: String,
+    base_url: String,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    proxy: Option<String>,
+    user_agent: Option<String>,
+    tls_backend: TlsBackend,
+    https_only: bool,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    retry_policy: Option<RetryPolicy>,
+    cache: Option<Arc<ResponseCache>>,
+    transport: Option<Arc<dyn Transport>>,
+}
+
+impl MetroHeroClientBuilder {
+    /// Start building a client which authenticates with the given API key.
+    pub fn new(api_key: String) -> Self {
+        Self {
+            api_key,
+            base_url: String::from(API_URL_BASE),
+            connect_timeout: None,
+            timeout: None,
+            proxy: None,
+            user_agent: None,
+            tls_backend: TlsBackend::default(),
+            https_only: true,
+            rate_limiter: None,
+            retry_policy: None,
+            cache: None,
+            transport: None,
+        }
+    }
+
+    /// Set the base URL requests are sent to. Defaults to `https://dcmetrohero.com/api/v1`.
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Set a timeout for establishing a connection to the API.
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Set a timeout for each request, from sending it until its response has been read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Send all requests through the HTTP(S) proxy at the given URL.
+    pub fn proxy(mut self, proxy_url: impl Into<String>) -> Self {
+        self.proxy = Some(proxy_url.into());
+        self
+    }
+
+    /// Set the `User-Agent` header sent with each request.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Set the TLS implementation used for HTTPS connections.
+    pub fn tls_backend(mut self, tls_backend: TlsBackend) -> Self {
+        self.tls_backend = tls_backend;
+        self
+    }
+
+    /// Refuse to send requests over plain HTTP. Defaults to `true`.
+    pub fn https_only(mut self, https_only: bool) -> Self {
+        self.https_only = https_only;
+        self
+    }
+
+    /// Throttle requests with the given rate limiter.
+    ///
+    /// Pass an `Arc<RateLimiter>` to share one limiter between several clients using the same API
+    /// key. By default, requests are not throttled.
+    pub fn rate_limiter(mut self, rate_limiter: impl Into<Arc<RateLimiter>>) -> Self {
+        self.rate_limiter = Some(rate_limiter.into());
+        self
+    }
+
+    /// Retry failed requests according to the given policy. By default, requests are not retried.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Cache responses in the given cache. By default, responses are not cached.
+    ///
+    /// Pass an `Arc<ResponseCache>` to share one cache between several clients.
+    pub fn cache(mut self, cache: impl Into<Arc<ResponseCache>>) -> Self {
+        self.cache = Some(cache.into());
+        self
+    }
+
+    /// Send requests through a custom [`Transport`] instead of over HTTP.
+    ///
+    /// The base URL, timeouts, proxy, user agent and TLS settings are not used by custom transports.
+    /// Custom transports are only supported by the blocking client.
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Build a blocking [`MetroHeroClient`].
+    pub fn build(self) -> Result<MetroHeroClient, MetroHeroError> {
+        let transport = match &self.transport {
+            Some(transport) => transport.clone(),
+            None => {
+                let http_client = configure_http_client!(&self, Client::builder()).build()?;
+                Arc::new(ReqwestTransport::new(http_client, &self.base_url))
+            }
+        };
+        Ok(MetroHeroClient {
+            transport,
+            rate_limiter: self.rate_limiter,
+            retry_policy: self.retry_policy,
+            cache: self.cache,
+        })
+    }
+
+    /// Build an [`AsyncMetroHeroClient`](crate::AsyncMetroHeroClient). Requires the `async`
+    /// feature.
+    #[cfg(feature = "async")]
+    pub fn build_async(self) -> Result<AsyncMetroHeroClient, MetroHeroError> {
+        let http_client = configure_http_client!(&self, reqwest::Client::builder()).build()?;
+        Ok(AsyncMetroHeroClient {
+            http_client,
+            api_url_base: self.base_url,
+            rate_limiter: self.rate_limiter,
+            retry_policy: self.retry_policy,
+            cache: self.cache,
+        })
+    }
+}
 
 /// A client for requesting data from the MetroHero API.
 ///
@@ -22,6 +398,8 @@ use crate::schemas::{
 /// When instantiated using [`MetroHeroClient::default()`], the client will attempt to fetch an API
 /// key from the environment at `METROHERO_API_KEY`. To set a key explicitely, use [`MetroHeroClient::new()`]
 ///
+/// To configure timeouts, proxies, or the API's base URL, use [`MetroHeroClient::builder()`].
+///
 /// # Example
 ///
 /// ```
@@ -30,66 +408,88 @@ use crate::schemas::{
 /// let system_metrics = client.get_system_metrics().unwrap();
 /// ```
 pub struct MetroHeroClient {
-    http_client: Client,
-    api_url_base: String,
+    transport: Arc<dyn Transport>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    retry_policy: Option<RetryPolicy>,
+    cache: Option<Arc<ResponseCache>>,
 }
 
 impl MetroHeroClient {
     /// Instantiate a new client for interacting with the MetroHero API.
     pub fn new(api_key: String) -> Self {
-        #[cfg(not(test))]
-        let api_url_base: &String = &String::from("https://dcmetrohero.com/api/v1");
+        MetroHeroClientBuilder::new(api_key)
+            .build()
+            .expect("Failed to initialize HTTP client")
+    }
 
-        #[cfg(test)]
-        let api_url_base: &String = &mockito::server_url();
+    /// Start building a client with custom settings.
+    pub fn builder(api_key: String) -> MetroHeroClientBuilder {
+        MetroHeroClientBuilder::new(api_key)
+    }
 
-        let mut headers = HeaderMap::new();
-        headers.insert("apiKey", api_key.parse().unwrap());
-        headers.insert(ACCEPT, "application/json".parse().unwrap());
+    /// Get the requests which may still be made before the client's rate limiter throttles them.
+    ///
+    /// Returns `None` if the client was not built with a rate limiter.
+    pub fn remaining_quota(&self) -> Option<Quota> {
+        self.rate_limiter
+            .as_ref()
+            .map(|rate_limiter| rate_limiter.remaining())
+    }
 
-        #[cfg(not(test))]
-        let require_tls = true;
+    /// Get the client's response cache, if it was built with one.
+    pub fn cache(&self) -> Option<&ResponseCache> {
+        self.cache.as_deref()
+    }
 
-        #[cfg(test)]
-        let require_tls = false;
+    /// Get the transport the client sends requests through.
+    ///
+    /// This can be wrapped, e.g. in a [`RecordingTransport`](crate::transport::RecordingTransport),
+    /// to build another client sharing the same connection and credentials.
+    pub fn transport(&self) -> Arc<dyn Transport> {
+        Arc::clone(&self.transport)
+    }
 
-        #[cfg(feature = "rustls")]
-        let client = Client::builder()
-            .default_headers(headers)
-            .use_rustls_tls()
-            .https_only(require_tls)
-            .min_tls_version(tls::Version::TLS_1_2)
-            .build()
-            .unwrap();
+    /// Send a request to the MetroHero API, retrying failures allowed by the retry policy.
+    fn send_request<T: DeserializeOwned>(&self, endpoint: Endpoint) -> Result<T, MetroHeroError> {
+        let request_path = endpoint.path();
+        if let Some(body) = self.cache().and_then(|cache| cache.get(&request_path)) {
+            return parse_body(&request_path, &body);
+        }
 
-        #[cfg(not(feature = "rustls"))]
-        let client = Client::builder()
-            .default_headers(headers)
-            .https_only(require_tls)
-            .min_tls_version(tls::Version::TLS_1_2)
-            .build()
-            .unwrap();
+        let mut attempt = 1;
+        let body = loop {
+            match self.fetch(&endpoint, &request_path) {
+                Ok(body) => break body,
+                Err(error) => {
+                    let delay = self
+                        .retry_policy
+                        .as_ref()
+                        .and_then(|policy| policy.next_delay(&request_path, attempt, &error));
+                    match delay {
+                        Some(delay) => thread::sleep(delay),
+                        None => return Err(error),
+                    }
+                    attempt += 1;
+                }
+            }
+        };
 
-        Self {
-            http_client: client,
-            api_url_base: api_url_base.clone(),
+        let model = parse_body(&request_path, &body)?;
+        if let Some(cache) = self.cache() {
+            cache.insert(&request_path, endpoint.family(), body);
         }
+        Ok(model)
     }
 
-    /// Send a request to the MetroHero API.
-    fn send_request<T: DeserializeOwned>(&self, request_path: String) -> Result<T, MetroHeroError> {
-        let request_url = format!("{}{}", self.api_url_base, request_path);
-        let response = self.http_client.get(request_url).send()?;
-        match response.status().as_u16() {
-            200 => {
-                let model: T = response.json().map_err(|_| MetroHeroError::ParseError)?;
-                Ok(model)
-            }
-            400 => Err(MetroHeroError::InvalidRequest),
-            401 => Err(MetroHeroError::AuthenticationError),
-            503 => Err(MetroHeroError::RateLimited),
-            _ => panic!("An unspecified status was received from th the server."),
+    /// Make a single attempt at a request, returning the body of a successful response.
+    fn fetch(&self, endpoint: &Endpoint, request_path: &str) -> Result<String, MetroHeroError> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            thread::sleep(rate_limiter.reserve()?);
         }
+
+        let response = self.transport.get(request_path)?;
+        check_response(endpoint, response.status, &response.body)?;
+        Ok(response.body)
     }
 
     /// Gets real-time system-wide metrics, broken down by line and direction of travel.
@@ -101,9 +501,7 @@ impl MetroHeroClient {
     ///
     /// See: <https://dcmetrohero.com/apis#operation--metrorail-metrics-get>
     pub fn get_system_metrics(&self) -> Result<SystemMetricsResponse, MetroHeroError> {
-        let api_path = String::from("/metrorail/metrics");
-        let metrics: SystemMetricsResponse = self.send_request(api_path)?;
-        Ok(metrics)
+        self.send_request(Endpoint::SystemMetrics)
     }
 
     /// Gets real-time trip information given current conditions.
@@ -114,7 +512,7 @@ impl MetroHeroClient {
     /// in the recent past into account--including any train delays and congestion--to make
     /// predictions about how long riders may be waiting or have been waiting for the next train to
     /// service the specified trip, as well as how long the trip might take once they're aboard.
-
+    ///
     /// Trips with station transfers are not directly supported. For example, to get trip
     /// information from Glenmont to Vienna, split the trip up into segments (e.g. Glenmont to
     /// Metro Center, then Metro Center to Vienna) and perform a separate request to this API for
@@ -127,14 +525,7 @@ impl MetroHeroClient {
         from_station_code: &StationCode,
         to_station_code: &StationCode,
     ) -> Result<TripInfo, MetroHeroError> {
-        let api_path = format!("/metrorail/trips/{}/{}", from_station_code, to_station_code);
-        let result = self.send_request(api_path);
-        match result {
-            Ok(trip_info) => Ok(trip_info),
-            // Invalid request here means one or more station codes were invalid.
-            Err(MetroHeroError::InvalidRequest) => Err(MetroHeroError::InvalidItinerary),
-            Err(e) => Err(e),
-        }
+        self.send_request(Endpoint::TripInfo(*from_station_code, *to_station_code))
     }
     /// Gets the last 30 minutes' worth of Metrorail-related tweets from Twitter.
     ///
@@ -145,9 +536,7 @@ impl MetroHeroClient {
     ///
     /// See: <https://dcmetrohero.com/apis#operation--metrorail-tweets-get>
     pub fn get_tweets(&self) -> Result<Vec<Tweet>, MetroHeroError> {
-        let api_path = String::from("/metrorail/tweets");
-        let tweets: Vec<Tweet> = self.send_request(api_path)?;
-        Ok(tweets)
+        self.send_request(Endpoint::Tweets)
     }
 
     /// Gets real-time train predictions for the entire Metrorail system.
@@ -163,9 +552,7 @@ impl MetroHeroClient {
     ///
     /// See: <https://dcmetrohero.com/apis#operation--metrorail-trains-get>
     pub fn get_train_positions(&self) -> Result<Vec<TrainPrediction>, MetroHeroError> {
-        let api_path = String::from("/metrorail/trains");
-        let train_positions: Vec<TrainPrediction> = self.send_request(api_path)?;
-        Ok(train_positions)
+        self.send_request(Endpoint::TrainPositions)
     }
 
     /// Gets real-time rider reports, referred to as tags, for all trains.
@@ -180,9 +567,7 @@ impl MetroHeroClient {
     ///
     /// See: <https://dcmetrohero.com/apis#operation--metrorail-trains-tags-get>
     pub fn get_train_reports(&self) -> Result<TrainReports, MetroHeroError> {
-        let api_path = String::from("/metrorail/trains/tags");
-        let reports: TrainReports = self.send_request(api_path)?;
-        Ok(reports)
+        self.send_request(Endpoint::TrainReports)
     }
 
     /// Gets real-time rider reports about a particular train, referred to as tags.
@@ -197,14 +582,7 @@ impl MetroHeroClient {
     /// Train tags are ordered by tag type in descending order by current number of active tags.
     /// See: <https://dcmetrohero.com/apis#operation--metrorail-trains--trainId--tags-get>
     pub fn get_train_report(&self, train_id: String) -> Result<TrainTags, MetroHeroError> {
-        let api_path = format!("/metrorail/trains/{train_id}/tags");
-        let result: Result<TrainTags, MetroHeroError> = self.send_request(api_path);
-        match result {
-            Ok(train_tags) => Ok(train_tags),
-            // Invalid request here can only mean the train ID was invalid.
-            Err(MetroHeroError::InvalidRequest) => Err(MetroHeroError::InvalidTrainId),
-            Err(e) => Err(e),
-        }
+        self.send_request(Endpoint::TrainReport(train_id))
     }
 
     /// Gets real-time and scheduled train predictions for all stations.
@@ -228,9 +606,7 @@ impl MetroHeroClient {
     ///
     /// See: <https://dcmetrohero.com/apis#operation--metrorail-stations-trains-get>
     pub fn get_train_predictions(&self) -> Result<TrainPredictions, MetroHeroError> {
-        let api_path = "/metrorail/stations/trains".to_string();
-        let predictions: TrainPredictions = self.send_request(api_path)?;
-        Ok(predictions)
+        self.send_request(Endpoint::TrainPredictions)
     }
 
     /// Gets real-time and scheduled train predictions for a particular station.
@@ -257,18 +633,7 @@ impl MetroHeroClient {
         &self,
         station_code: &StationCode,
     ) -> Result<Vec<TrainPrediction>, MetroHeroError> {
-        let api_path = format!(
-            "/metrorail/stations/{}/trains?includeScheduledPredictions=true",
-            station_code
-        );
-
-        let result: Result<Vec<TrainPrediction>, MetroHeroError> = self.send_request(api_path);
-        match result {
-            Ok(train_predictions) => Ok(train_predictions),
-            // 400 Errors here indicate that the station ID was invalid.
-            Err(MetroHeroError::InvalidRequest) => Err(MetroHeroError::InvalidStation),
-            Err(e) => Err(e),
-        }
+        self.send_request(Endpoint::StationTrainPredictions(*station_code))
     }
 
     /// Gets real-time rider reports, referred to as tags, for all stations.
@@ -284,9 +649,7 @@ impl MetroHeroClient {
     /// Station tags are ordered by tag type in descending order by current number of active tags.
     /// See: <https://dcmetrohero.com/apis#operation--metrorail-stations-tags-get>
     pub fn get_station_reports(&self) -> Result<StationReports, MetroHeroError> {
-        let api_path = "/metrorail/stations/tags".to_string();
-        let reports: StationReports = self.send_request(api_path)?;
-        Ok(reports)
+        self.send_request(Endpoint::StationReports)
     }
 
     /// Gets real-time rider reports about a particular station, referred to as tags.
@@ -302,40 +665,343 @@ impl MetroHeroClient {
         &self,
         station_code: &StationCode,
     ) -> Result<StationTags, MetroHeroError> {
-        let api_path = format!("/metrorail/stations/{}/tags", station_code);
-        let result: Result<StationTags, MetroHeroError> = self.send_request(api_path);
-        match result {
-            Ok(station_tags) => Ok(station_tags),
-            // If request was invalid, only explanation is that the station code was invalid.
-            Err(MetroHeroError::InvalidRequest) => Err(MetroHeroError::InvalidStation),
-            Err(e) => Err(e),
-        }
+        self.send_request(Endpoint::StationReport(*station_code))
     }
 }
 
 impl Default for MetroHeroClient {
     /// Instantiate a MetroHeroClient using an API key from the `METROHERO_API_KEY` environment variable.
     fn default() -> Self {
-        let api_key_env_var_name = "METROHERO_API_KEY";
-
-        #[cfg(not(test))]
-        let api_key = env::var(api_key_env_var_name)
-            .unwrap_or_else(|_| panic!("Environment variable {api_key_env_var_name} is missing"));
-
-        #[cfg(test)]
-        let api_key = String::from(api_key_env_var_name);
-
-        MetroHeroClient::new(api_key)
+        MetroHeroClient::new(api_key_from_env())
     }
 }
 
 #[cfg(test)]
-mod tests {
+#[allow(missing_docs)]
+pub mod tests {
+    use crate::cache::ResponseCache;
     use crate::client::MetroHeroClient;
     use crate::errors::MetroHeroError;
+    use crate::ratelimit::RateLimiter;
+    use crate::retry::RetryPolicy;
     use crate::schemas::tests::get_test_data;
     use crate::schemas::StationCode;
     use mockito::mock;
+    use rstest::rstest;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// Get a client which sends requests to the mockito server.
+    pub fn test_client() -> MetroHeroClient {
+        MetroHeroClient::builder(String::from("METROHERO_API_KEY"))
+            .base_url(mockito::server_url())
+            .https_only(false)
+            .build()
+            .unwrap()
+    }
+
+    /// Check that statuses without a specific meaning are returned as errors, not panics.
+    #[rstest]
+    #[case::not_found(404)]
+    #[case::internal_server_error(500)]
+    #[case::bad_gateway(502)]
+    #[case::gateway_timeout(504)]
+    fn test_unexpected_status(#[case] status: usize) {
+        let mock = mock("GET", "/metrorail/metrics")
+            .with_status(status)
+            .with_body("Something went wrong")
+            .create();
+
+        let client = test_client();
+        let err = client.get_system_metrics().unwrap_err();
+        assert_eq!(
+            err,
+            MetroHeroError::UnexpectedStatus {
+                request_path: Some(String::from("/metrorail/metrics")),
+                status: status as u16,
+                body: String::from("Something went wrong")
+            }
+        );
+        mock.assert();
+    }
+
+    #[rstest]
+    #[case::too_many_requests(429)]
+    #[case::service_unavailable(503)]
+    fn test_rate_limited_status(#[case] status: usize) {
+        let mock = mock("GET", "/metrorail/tweets")
+            .with_status(status)
+            .create();
+
+        let client = test_client();
+        let err = client.get_tweets().unwrap_err();
+        assert_eq!(
+            err,
+            MetroHeroError::RateLimited {
+                request_path: Some(String::from("/metrorail/tweets"))
+            }
+        );
+        mock.assert();
+    }
+
+    #[test]
+    fn test_unexpected_status_body_truncated() {
+        let mock = mock("GET", "/metrorail/trains")
+            .with_status(500)
+            .with_body("x".repeat(1000))
+            .create();
+
+        let client = test_client();
+        let err = client.get_train_positions().unwrap_err();
+        match err {
+            MetroHeroError::UnexpectedStatus {
+                request_path,
+                status,
+                body,
+            } => {
+                assert_eq!(request_path.as_deref(), Some("/metrorail/trains"));
+                assert_eq!(status, 500);
+                assert_eq!(body, format!("{}...", "x".repeat(256)));
+            }
+            _ => panic!("Expected UnexpectedStatus, got {err:?}"),
+        }
+        mock.assert();
+    }
+
+    #[test]
+    fn test_builder_settings() {
+        let mock = mock("GET", "/metrorail/tweets")
+            .match_header("apiKey", "MY_KEY")
+            .match_header("user-agent", "metrohero-test/1.0")
+            .with_status(200)
+            .with_body("[]")
+            .create();
+
+        let client = MetroHeroClient::builder(String::from("MY_KEY"))
+            .base_url(format!("{}/", mockito::server_url()))
+            .https_only(false)
+            .user_agent("metrohero-test/1.0")
+            .connect_timeout(Duration::from_secs(5))
+            .timeout(Duration::from_secs(5))
+            .build()
+            .unwrap();
+        client.get_tweets().unwrap();
+        mock.assert();
+    }
+
+    #[test]
+    fn test_builder_https_only() {
+        let client = MetroHeroClient::builder(String::from("MY_KEY"))
+            .base_url(mockito::server_url())
+            .build()
+            .unwrap();
+        let err = client.get_tweets().unwrap_err();
+        assert!(matches!(err, MetroHeroError::HttpError { .. }));
+    }
+
+    #[test]
+    fn test_builder_invalid_proxy() {
+        let result = MetroHeroClient::builder(String::from("MY_KEY"))
+            .proxy("not a url")
+            .build();
+        assert!(matches!(result, Err(MetroHeroError::HttpError { .. })));
+    }
+
+    #[test]
+    fn test_rate_limiter_daily_budget() {
+        let mock = mock("GET", "/metrorail/tweets")
+            .with_status(200)
+            .with_body("[]")
+            .expect(2)
+            .create();
+
+        let client = MetroHeroClient::builder(String::from("METROHERO_API_KEY"))
+            .base_url(mockito::server_url())
+            .https_only(false)
+            .rate_limiter(RateLimiter::new(10, 2))
+            .build()
+            .unwrap();
+        client.get_tweets().unwrap();
+        client.get_tweets().unwrap();
+        assert_eq!(client.remaining_quota().unwrap().remaining_today, 0);
+
+        // The third request is refused without being sent.
+        let err = client.get_tweets().unwrap_err();
+        assert!(
+            matches!(err, MetroHeroError::QuotaExhausted { .. }),
+            "{err:?}"
+        );
+        mock.assert();
+    }
+
+    #[test]
+    fn test_remaining_quota_without_rate_limiter() {
+        assert_eq!(test_client().remaining_quota(), None);
+    }
+
+    /// Get a client which retries requests to the mockito server without delay.
+    fn retrying_client(retry_policy: RetryPolicy) -> MetroHeroClient {
+        MetroHeroClient::builder(String::from("METROHERO_API_KEY"))
+            .base_url(mockito::server_url())
+            .https_only(false)
+            .retry_policy(retry_policy.base_delay(Duration::ZERO))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_retry_transient_failures() {
+        let failing_mock = mock("GET", "/metrorail/metrics")
+            .with_status(503)
+            .expect(2)
+            .create();
+        let mock = mock("GET", "/metrorail/metrics")
+            .with_status(200)
+            .with_body(get_test_data(String::from("system_metrics_api.json")))
+            .create();
+
+        let retries = Arc::new(Mutex::new(vec![]));
+        let observed_retries = retries.clone();
+        let client = retrying_client(RetryPolicy::new(3).on_retry(move |event| {
+            observed_retries
+                .lock()
+                .unwrap()
+                .push((event.attempt, event.error.to_string()));
+        }));
+        client.get_system_metrics().unwrap();
+
+        assert_eq!(
+            *retries.lock().unwrap(),
+            vec![
+                (
+                    1,
+                    MetroHeroError::RateLimited { request_path: None }.to_string()
+                ),
+                (
+                    2,
+                    MetroHeroError::RateLimited { request_path: None }.to_string()
+                )
+            ]
+        );
+        failing_mock.assert();
+        mock.assert();
+    }
+
+    #[test]
+    fn test_retry_gives_up_after_max_attempts() {
+        let mock = mock("GET", "/metrorail/tweets")
+            .with_status(500)
+            .expect(2)
+            .create();
+
+        let client = retrying_client(RetryPolicy::new(2));
+        let err = client.get_tweets().unwrap_err();
+        assert!(matches!(
+            err,
+            MetroHeroError::UnexpectedStatus { status: 500, .. }
+        ));
+        mock.assert();
+    }
+
+    #[test]
+    fn test_retry_skips_permanent_failures() {
+        let mock = mock("GET", "/metrorail/stations/UNKNOWN/tags")
+            .with_status(400)
+            .expect(1)
+            .create();
+
+        let client = retrying_client(RetryPolicy::new(3));
+        let err = client
+            .get_station_report(&StationCode::UNKNOWN)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            MetroHeroError::InvalidStation {
+                request_path: Some(String::from("/metrorail/stations/UNKNOWN/tags"))
+            }
+        );
+        mock.assert();
+    }
+
+    #[test]
+    fn test_retry_custom_predicate() {
+        let mock = mock("GET", "/metrorail/tweets")
+            .with_status(404)
+            .expect(3)
+            .create();
+
+        let client = retrying_client(RetryPolicy::new(3).retry_if(|error| {
+            matches!(error, MetroHeroError::UnexpectedStatus { status: 404, .. })
+        }));
+        client.get_tweets().unwrap_err();
+        mock.assert();
+    }
+
+    #[test]
+    fn test_cache_serves_repeated_requests() {
+        let mock = mock("GET", "/metrorail/stations/K04/tags")
+            .with_status(200)
+            .with_body(get_test_data("station_tags_api.json".to_string()))
+            .expect(2)
+            .create();
+
+        let client = MetroHeroClient::builder(String::from("METROHERO_API_KEY"))
+            .base_url(mockito::server_url())
+            .https_only(false)
+            .cache(ResponseCache::new())
+            .build()
+            .unwrap();
+        client.get_station_report(&StationCode::K04).unwrap();
+        client.get_station_report(&StationCode::K04).unwrap();
+
+        let cache = client.cache().unwrap();
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(cache.stats().misses, 1);
+
+        // Once invalidated, the next request goes to the API again.
+        cache.invalidate("/metrorail/stations/K04/tags");
+        client.get_station_report(&StationCode::K04).unwrap();
+        assert_eq!(cache.stats().misses, 2);
+        mock.assert();
+    }
+
+    #[test]
+    fn test_cache_skips_errors() {
+        let mock = mock("GET", "/metrorail/metrics")
+            .with_status(500)
+            .expect(2)
+            .create();
+
+        let client = MetroHeroClient::builder(String::from("METROHERO_API_KEY"))
+            .base_url(mockito::server_url())
+            .https_only(false)
+            .cache(ResponseCache::new())
+            .build()
+            .unwrap();
+        client.get_system_metrics().unwrap_err();
+        client.get_system_metrics().unwrap_err();
+        assert_eq!(client.cache().unwrap().stats().entries, 0);
+        mock.assert();
+    }
+
+    #[test]
+    fn test_parse_error_context() {
+        let mock = mock("GET", "/metrorail/tweets")
+            .with_status(200)
+            .with_body(r#"[{"twitterId": "not a number"}]"#)
+            .create();
+
+        let client = test_client();
+        let err = client.get_tweets().unwrap_err();
+        assert_eq!(err.request_path(), Some("/metrorail/tweets"));
+        match err {
+            MetroHeroError::ParseError { field_path, .. } => {
+                assert_eq!(field_path.as_deref(), Some("[0].twitterId"))
+            }
+            _ => panic!("Expected ParseError, got {err:?}"),
+        }
+        mock.assert();
+    }
 
     #[test]
     fn test_get_station_train_predictions_invalid() {
@@ -346,11 +1012,18 @@ mod tests {
         .with_status(400)
         .create();
 
-        let client = MetroHeroClient::default();
+        let client = test_client();
         let err = client
             .get_station_train_predictions(&StationCode::UNKNOWN)
             .unwrap_err();
-        assert_eq!(err, MetroHeroError::InvalidStation);
+        assert_eq!(
+            err,
+            MetroHeroError::InvalidStation {
+                request_path: Some(String::from(
+                    "/metrorail/stations/UNKNOWN/trains?includeScheduledPredictions=true"
+                ))
+            }
+        );
         mock.assert();
     }
 
@@ -360,7 +1033,7 @@ mod tests {
             .with_status(200)
             .with_body(get_test_data(String::from("tripinfo_api.json")))
             .create();
-        let client = MetroHeroClient::default();
+        let client = test_client();
         client
             .get_trip_info(&StationCode::K01, &StationCode::K02)
             .unwrap();
@@ -373,11 +1046,16 @@ mod tests {
             .with_status(400)
             .create();
 
-        let client = MetroHeroClient::default();
+        let client = test_client();
         let error = client
             .get_trip_info(&StationCode::UNKNOWN, &StationCode::UNKNOWN)
             .unwrap_err();
-        assert_eq!(error, MetroHeroError::InvalidItinerary);
+        assert_eq!(
+            error,
+            MetroHeroError::InvalidItinerary {
+                request_path: Some(String::from("/metrorail/trips/UNKNOWN/UNKNOWN"))
+            }
+        );
         mock.assert()
     }
 
@@ -388,7 +1066,7 @@ mod tests {
             .with_body(get_test_data(String::from("system_metrics_api.json")))
             .create();
 
-        let client = MetroHeroClient::default();
+        let client = test_client();
         client.get_system_metrics().unwrap();
         mock.assert();
     }
@@ -399,7 +1077,7 @@ mod tests {
             .with_status(200)
             .with_body(get_test_data(String::from("tweets_api.json")))
             .create();
-        let client = MetroHeroClient::default();
+        let client = test_client();
         client.get_tweets().unwrap();
         mock.assert();
     }
@@ -410,7 +1088,7 @@ mod tests {
             .with_status(200)
             .with_body(get_test_data(String::from("train_predictions_api.json")))
             .create();
-        let client = MetroHeroClient::default();
+        let client = test_client();
         client.get_train_positions().unwrap();
         mock.assert();
     }
@@ -420,9 +1098,32 @@ mod tests {
         let mock = mock("GET", "/metrorail/trains/123/tags")
             .with_status(400)
             .create();
-        let client = MetroHeroClient::default();
+        let client = test_client();
         let error = client.get_train_report(String::from("123")).unwrap_err();
-        assert_eq!(error, MetroHeroError::InvalidTrainId);
+        assert_eq!(
+            error,
+            MetroHeroError::InvalidTrainId {
+                request_path: Some(String::from("/metrorail/trains/123/tags"))
+            }
+        );
+        mock.assert();
+    }
+
+    #[test]
+    fn test_get_train_report_encodes_train_id() {
+        let mock = mock("GET", "/metrorail/trains/1%2F%2E%2E%3Fx%23/tags")
+            .with_status(400)
+            .create();
+        let client = test_client();
+        let error = client
+            .get_train_report(String::from("1/..?x#"))
+            .unwrap_err();
+        assert_eq!(
+            error,
+            MetroHeroError::InvalidTrainId {
+                request_path: Some(String::from("/metrorail/trains/1%2F%2E%2E%3Fx%23/tags"))
+            }
+        );
         mock.assert();
     }
 
@@ -432,7 +1133,7 @@ mod tests {
             .with_status(200)
             .with_body(get_test_data(String::from("global_train_predictions.json")))
             .create();
-        let client = MetroHeroClient::default();
+        let client = test_client();
         client.get_train_predictions().unwrap();
         mock.assert();
     }
@@ -449,7 +1150,7 @@ mod tests {
         )))
         .create();
 
-        let client = MetroHeroClient::default();
+        let client = test_client();
         client
             .get_station_train_predictions(&StationCode::K01)
             .unwrap();
@@ -462,7 +1163,7 @@ mod tests {
             .with_status(200)
             .with_body(get_test_data(String::from("global_station_reports.json")))
             .create();
-        let client = MetroHeroClient::default();
+        let client = test_client();
         client.get_station_reports().unwrap();
         mock.assert();
     }
@@ -473,7 +1174,7 @@ mod tests {
             .with_status(200)
             .with_body(get_test_data("station_tags_api.json".to_string()))
             .create();
-        let client = MetroHeroClient::default();
+        let client = test_client();
         client.get_station_report(&StationCode::K05).unwrap();
         mock.assert();
     }
@@ -483,11 +1184,16 @@ mod tests {
         let mock = mock("GET", "/metrorail/stations/UNKNOWN/tags")
             .with_status(400)
             .create();
-        let client = MetroHeroClient::default();
+        let client = test_client();
         let err = client
             .get_station_report(&StationCode::UNKNOWN)
             .unwrap_err();
-        assert_eq!(err, MetroHeroError::InvalidStation);
+        assert_eq!(
+            err,
+            MetroHeroError::InvalidStation {
+                request_path: Some(String::from("/metrorail/stations/UNKNOWN/tags"))
+            }
+        );
         mock.assert();
     }
 }
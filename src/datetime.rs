@@ -0,0 +1,131 @@
+#![warn(missing_docs)]
+//! Parsing the timestamps returned by the MetroHero API. Requires the `chrono` feature.
+//!
+//! The API emits timestamps in two formats: RFC 3339 timestamps with a UTC offset, e.g.
+//! `2022-11-06T12:02:01.903-05:00`, and Java-style local times in Washington, DC, e.g.
+//! `Nov 13, 2022, 5:36:51 PM`. Both are parsed into `DateTime`s in the `America/New_York`
+//! timezone. Response fields keep the raw strings, and typed accessors such as
+//! [`Tweet::date_time`](crate::schemas::Tweet::date_time) parse them on demand.
+use std::cmp::Ordering;
+
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::America::New_York;
+use chrono_tz::Tz;
+
+/// Formats of the local times emitted by the API, with and without a comma after the year.
+const LOCAL_FORMATS: [&str; 2] = ["%b %d, %Y, %I:%M:%S %p", "%b %d, %Y %I:%M:%S %p"];
+
+/// Parse a timestamp in any format emitted by the MetroHero API.
+///
+/// Returns `None` if the timestamp is not in a known format, or names a local time which does not
+/// exist in Washington, DC because of a daylight saving time transition.
+///
+/// ```
+/// use metrohero_rs::datetime::parse_timestamp;
+///
+/// let with_offset = parse_timestamp("2022-11-13T17:36:51-05:00").unwrap();
+/// let local = parse_timestamp("Nov 13, 2022, 5:36:51 PM").unwrap();
+/// assert_eq!(with_offset, local);
+/// ```
+pub fn parse_timestamp(timestamp: &str) -> Option<DateTime<Tz>> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(timestamp) {
+        return Some(datetime.with_timezone(&New_York));
+    }
+
+    // Newer JVMs separate the time from AM/PM with a narrow no-break space.
+    let timestamp = timestamp.replace('\u{202f}', " ");
+    LOCAL_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(&timestamp, format).ok())
+        .and_then(|naive| New_York.from_local_datetime(&naive).earliest())
+}
+
+/// Compare timestamps in any format emitted by the MetroHero API by when they happened.
+///
+/// Falls back to comparing the raw strings if either timestamp cannot be parsed.
+pub fn cmp_timestamps(timestamp: &str, other: &str) -> Ordering {
+    match (parse_timestamp(timestamp), parse_timestamp(other)) {
+        (Some(datetime), Some(other_datetime)) => datetime.cmp(&other_datetime),
+        _ => timestamp.cmp(other),
+    }
+}
+
+/// How long ago a timestamp was, e.g. the age of an observation.
+pub fn age(timestamp: &DateTime<Tz>) -> Duration {
+    Utc::now().signed_duration_since(*timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::datetime::{age, cmp_timestamps, parse_timestamp};
+    use crate::schemas::TrainPrediction;
+    use chrono::{DateTime, Duration};
+    use chrono_tz::America::New_York;
+    use rstest::rstest;
+    use std::cmp::Ordering;
+
+    #[rstest]
+    #[case::rfc3339("2022-11-06T07:04:27-05:00", "2022-11-06T12:04:27Z")]
+    #[case::rfc3339_millis("2022-11-12T16:56:38.679-05:00", "2022-11-12T21:56:38.679Z")]
+    #[case::local("Nov 13, 2022, 5:36:51 PM", "2022-11-13T22:36:51Z")]
+    #[case::local_summer_time("Aug 11, 2017 3:51:54 PM", "2017-08-11T19:51:54Z")]
+    #[case::local_narrow_space("Nov 13, 2022, 9:26:54\u{202f}AM", "2022-11-13T14:26:54Z")]
+    fn test_parse_timestamp(#[case] timestamp: &str, #[case] expected: &str) {
+        let parsed = parse_timestamp(timestamp).unwrap();
+        assert_eq!(parsed, DateTime::parse_from_rfc3339(expected).unwrap());
+        assert_eq!(parsed.timezone(), New_York);
+    }
+
+    #[test]
+    fn test_ambiguous_local_time_is_earliest() {
+        // Clocks fell back from 2:00 to 1:00 EDT on Nov 6, 2022, so 1:30 AM happened twice.
+        let parsed = parse_timestamp("Nov 6, 2022, 1:30:00 AM").unwrap();
+        assert_eq!(parsed.to_rfc3339(), "2022-11-06T01:30:00-04:00");
+    }
+
+    #[rstest]
+    #[case::garbage("yesterday")]
+    #[case::skipped_local_time("Mar 13, 2022, 2:30:00 AM")]
+    fn test_invalid_timestamp(#[case] timestamp: &str) {
+        assert_eq!(parse_timestamp(timestamp), None);
+    }
+
+    #[test]
+    fn test_observation_age() {
+        let json = crate::schemas::tests::get_test_data(String::from("train_predictions_api.json"));
+        let predictions: Vec<TrainPrediction> = serde_json::from_str(&json).unwrap();
+        let observed = predictions[0].observed_date_time().unwrap();
+        assert!(predictions[0].observation_age().unwrap() > Duration::days(365));
+        assert!(age(&observed) > Duration::days(365));
+    }
+
+    #[test]
+    fn test_unknown_format_is_none() {
+        let json = crate::schemas::tests::get_test_data(String::from("train_predictions_api.json"))
+            .replacen("\"observedDate\": \"", "\"observedDate\": \"around ", 1);
+        let predictions: Vec<TrainPrediction> = serde_json::from_str(&json).unwrap();
+        assert_eq!(predictions[0].observed_date_time(), None);
+        assert_eq!(predictions[0].observation_age(), None);
+    }
+
+    #[rstest]
+    #[case::different_offsets(
+        "2022-11-13T17:36:51-05:00",
+        "2022-11-13T23:00:00+01:00",
+        Ordering::Greater
+    )]
+    #[case::different_formats(
+        "Nov 13, 2022, 5:36:51 PM",
+        "2022-11-13T12:00:00-05:00",
+        Ordering::Greater
+    )]
+    #[case::same_instant("Nov 13, 2022, 5:36:51 PM", "2022-11-13T22:36:51Z", Ordering::Equal)]
+    #[case::unparseable("yesterday", "2022-11-13T12:00:00-05:00", Ordering::Greater)]
+    fn test_cmp_timestamps(
+        #[case] timestamp: &str,
+        #[case] other: &str,
+        #[case] expected: Ordering,
+    ) {
+        assert_eq!(cmp_timestamps(timestamp, other), expected);
+    }
+}
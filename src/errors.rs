@@ -1,54 +1,286 @@
 //! Errors emitted by the MetroHero API.
-use std::fmt;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{fmt, io};
 
 /// Errors relating to communication with the MetroHero API.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug)]
 pub enum MetroHeroError {
-    HttpError,
-    ParseError,
-    InvalidRequest,
-    InvalidStation,
-    InvalidTrainId,
-    InvalidItinerary,
-    AuthenticationError,
-    RateLimited,
+    /// A request could not be sent, or its response could not be read.
+    HttpError {
+        /// The path of the request that failed, if known.
+        request_path: Option<String>,
+        /// The underlying HTTP client error.
+        source: reqwest::Error,
+    },
+    /// A response or value could not be parsed into the expected schema.
+    ParseError {
+        /// The path of the request whose response could not be parsed, if known.
+        request_path: Option<String>,
+        /// The path to the offending field within the response (e.g. `[0].Line`), if known.
+        field_path: Option<String>,
+        /// The underlying parsing error.
+        source: Box<dyn Error + Send + Sync>,
+    },
+    InvalidRequest {
+        /// The path of the rejected request, if known.
+        request_path: Option<String>,
+    },
+    InvalidStation {
+        /// The path of the rejected request, if known.
+        request_path: Option<String>,
+    },
+    InvalidTrainId {
+        /// The path of the rejected request, if known.
+        request_path: Option<String>,
+    },
+    InvalidItinerary {
+        /// The path of the rejected request, if known.
+        request_path: Option<String>,
+    },
+    AuthenticationError {
+        /// The path of the rejected request, if known.
+        request_path: Option<String>,
+    },
+    RateLimited {
+        /// The path of the rejected request, if known.
+        request_path: Option<String>,
+    },
+    /// The client's own rate limiter refused to send a request because its rolling 24-hour budget
+    /// has been spent. Unlike [`MetroHeroError::RateLimited`], no request reached the API.
+    QuotaExhausted {
+        /// How long until the oldest request leaves the 24-hour window, freeing part of the budget.
+        retry_after: Duration,
+    },
+    /// The API responded with a status code that has no more specific meaning.
+    UnexpectedStatus {
+        /// The path of the rejected request, if known.
+        request_path: Option<String>,
+        /// The HTTP status code of the response.
+        status: u16,
+        /// The start of the response body, truncated to keep errors small.
+        body: String,
+    },
+    /// A recorded fixture could not be read or written.
+    FixtureError {
+        /// The fixture file or directory which could not be accessed.
+        path: PathBuf,
+        /// The underlying I/O error.
+        source: io::Error,
+    },
 }
 
-impl std::error::Error for MetroHeroError {}
+impl MetroHeroError {
+    /// Wrap an HTTP client error raised while requesting `request_path`.
+    pub(crate) fn http(request_path: &str, source: reqwest::Error) -> Self {
+        MetroHeroError::HttpError {
+            request_path: Some(request_path.to_string()),
+            source,
+        }
+    }
+
+    /// Wrap a deserialization error raised while parsing the response to `request_path`.
+    pub(crate) fn parse(
+        request_path: &str,
+        source: serde_path_to_error::Error<serde_json::Error>,
+    ) -> Self {
+        MetroHeroError::ParseError {
+            request_path: Some(request_path.to_string()),
+            field_path: Some(source.path().to_string()),
+            source: Box::new(source.into_inner()),
+        }
+    }
+
+    /// Wrap an I/O error raised while accessing the fixture at `path`.
+    pub(crate) fn fixture(path: &Path, source: io::Error) -> Self {
+        MetroHeroError::FixtureError {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    /// The path of the request which caused this error, if known.
+    pub fn request_path(&self) -> Option<&str> {
+        match self {
+            MetroHeroError::HttpError { request_path, .. }
+            | MetroHeroError::ParseError { request_path, .. }
+            | MetroHeroError::InvalidRequest { request_path }
+            | MetroHeroError::InvalidStation { request_path }
+            | MetroHeroError::InvalidTrainId { request_path }
+            | MetroHeroError::InvalidItinerary { request_path }
+            | MetroHeroError::AuthenticationError { request_path }
+            | MetroHeroError::RateLimited { request_path }
+            | MetroHeroError::UnexpectedStatus { request_path, .. } => request_path.as_deref(),
+            MetroHeroError::QuotaExhausted { .. } | MetroHeroError::FixtureError { .. } => None,
+        }
+    }
+}
+
+impl Error for MetroHeroError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MetroHeroError::HttpError { source, .. } => Some(source),
+            MetroHeroError::ParseError { source, .. } => Some(source.as_ref()),
+            MetroHeroError::FixtureError { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Errors are equal when they are of the same kind and carry the same context. Underlying
+/// sources and the time until a spent quota resets are not compared.
+impl PartialEq for MetroHeroError {
+    fn eq(&self, other: &Self) -> bool {
+        let same_context = match (self, other) {
+            (
+                MetroHeroError::ParseError { field_path, .. },
+                MetroHeroError::ParseError {
+                    field_path: other_field_path,
+                    ..
+                },
+            ) => field_path == other_field_path,
+            (
+                MetroHeroError::UnexpectedStatus { status, body, .. },
+                MetroHeroError::UnexpectedStatus {
+                    status: other_status,
+                    body: other_body,
+                    ..
+                },
+            ) => status == other_status && body == other_body,
+            (
+                MetroHeroError::FixtureError { path, .. },
+                MetroHeroError::FixtureError {
+                    path: other_path, ..
+                },
+            ) => path == other_path,
+            _ => true,
+        };
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+            && self.request_path() == other.request_path()
+            && same_context
+    }
+}
+
+impl Eq for MetroHeroError {}
 
 impl fmt::Display for MetroHeroError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            MetroHeroError::HttpError => write!(f, "Error while communicating with MetroHero API"),
-            MetroHeroError::ParseError => write!(f, "Error while parsing data from MetroHero API"),
-            MetroHeroError::InvalidRequest => write!(f, "Request to MetroHero API was invalid"),
-            MetroHeroError::InvalidStation => write!(f, "Provided station code or name is invalid"),
-            MetroHeroError::InvalidItinerary => write!(f, "Provided itinerary is invalid"),
-            MetroHeroError::AuthenticationError => {
+            MetroHeroError::HttpError { .. } => {
+                write!(f, "Error while communicating with MetroHero API")
+            }
+            MetroHeroError::ParseError { .. } => {
+                write!(f, "Error while parsing data from MetroHero API")
+            }
+            MetroHeroError::InvalidRequest { .. } => {
+                write!(f, "Request to MetroHero API was invalid")
+            }
+            MetroHeroError::InvalidStation { .. } => {
+                write!(f, "Provided station code or name is invalid")
+            }
+            MetroHeroError::InvalidItinerary { .. } => write!(f, "Provided itinerary is invalid"),
+            MetroHeroError::AuthenticationError { .. } => {
                 write!(f, "Provided MetroHero API key is invalid")
             }
-            MetroHeroError::RateLimited => {
+            MetroHeroError::RateLimited { .. } => {
                 write!(f, "Too many requests, limit is: 10/s and 50k/24hr")
             }
-            MetroHeroError::InvalidTrainId => write!(f, "Provided Train ID is not valid"),
+            MetroHeroError::InvalidTrainId { .. } => write!(f, "Provided Train ID is not valid"),
+            MetroHeroError::QuotaExhausted { retry_after } => write!(
+                f,
+                "Daily request budget spent, more requests allowed in {}s",
+                retry_after.as_secs()
+            ),
+            MetroHeroError::UnexpectedStatus { status, body, .. } => {
+                write!(f, "MetroHero API responded with status {status}: {body}")
+            }
+            MetroHeroError::FixtureError { path, .. } => {
+                write!(f, "Error while accessing fixture {}", path.display())
+            }
         }
     }
 }
 
 impl From<reqwest::Error> for MetroHeroError {
-    fn from(_: reqwest::Error) -> Self {
-        MetroHeroError::HttpError
+    fn from(source: reqwest::Error) -> Self {
+        MetroHeroError::HttpError {
+            request_path: None,
+            source,
+        }
     }
 }
 
 impl From<serde_json::Error> for MetroHeroError {
-    fn from(_: serde_json::Error) -> Self {
-        MetroHeroError::ParseError
+    fn from(source: serde_json::Error) -> Self {
+        MetroHeroError::ParseError {
+            request_path: None,
+            field_path: None,
+            source: Box::new(source),
+        }
     }
 }
 
 impl From<strum::ParseError> for MetroHeroError {
-    fn from(_: strum::ParseError) -> Self {
-        MetroHeroError::ParseError
+    fn from(source: strum::ParseError) -> Self {
+        MetroHeroError::ParseError {
+            request_path: None,
+            field_path: None,
+            source: Box::new(source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::MetroHeroError;
+    use crate::schemas::TrainPrediction;
+    use std::error::Error;
+
+    #[test]
+    fn test_parse_error_context() {
+        let body = r#"[{"trainId": 5}]"#;
+        let deserializer = &mut serde_json::Deserializer::from_str(body);
+        let source =
+            serde_path_to_error::deserialize::<_, Vec<TrainPrediction>>(deserializer).unwrap_err();
+        let error = MetroHeroError::parse("/metrorail/trains", source);
+
+        assert_eq!(error.request_path(), Some("/metrorail/trains"));
+        match &error {
+            MetroHeroError::ParseError { field_path, .. } => {
+                assert_eq!(field_path.as_deref(), Some("[0].trainId"))
+            }
+            _ => panic!("Expected ParseError, got {error:?}"),
+        }
+        assert!(error.source().is_some());
+        assert_eq!(
+            error.to_string(),
+            "Error while parsing data from MetroHero API"
+        );
+    }
+
+    #[test]
+    fn test_status_error_request_path() {
+        let error = MetroHeroError::InvalidStation {
+            request_path: Some(String::from("/metrorail/stations/UNKNOWN/tags")),
+        };
+        assert_eq!(
+            error.request_path(),
+            Some("/metrorail/stations/UNKNOWN/tags")
+        );
+        assert_ne!(error, MetroHeroError::InvalidStation { request_path: None });
+        assert_ne!(
+            error,
+            MetroHeroError::InvalidTrainId {
+                request_path: Some(String::from("/metrorail/stations/UNKNOWN/tags")),
+            }
+        );
+    }
+
+    #[test]
+    fn test_strum_error_source() {
+        let error = MetroHeroError::from(strum::ParseError::VariantNotFound);
+        assert!(error.source().unwrap().is::<strum::ParseError>());
+        assert_eq!(error.request_path(), None);
     }
 }
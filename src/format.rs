@@ -6,11 +6,18 @@ use comfy_table::{Cell, CellAlignment, Row, Table};
 use crossterm::style::{Attribute, Color, Stylize};
 use strum::IntoEnumIterator;
 
-use crate::schemas::{StationCode, StationTags, TrainPrediction, TripInfo};
+use crate::network::LINES;
+use crate::planner::Route;
+use crate::schemas::{
+    ElevatorEscalatorOutage, LineCode, MetroAlert, ServiceGaps, StationCode, StationTags,
+    SystemMetricsResponse, TrainPrediction, TrainTags, TripInfo, Tweet,
+};
+use crate::stations::NearbyStation;
 
 fn get_eta_cell(train_prediction: &TrainPrediction) -> Cell {
-    let mut cell = Cell::new(train_prediction.eta_minutes());
-    if train_prediction.min == "ARR" || train_prediction.min == "BRD" {
+    let eta = train_prediction.eta();
+    let mut cell = Cell::new(eta);
+    if eta.is_imminent() {
         cell = cell.add_attributes(vec![Attribute::SlowBlink, Attribute::Bold]);
     }
     cell
@@ -32,7 +39,9 @@ fn get_notes_cell(train_prediction: &TrainPrediction) -> Cell {
     Cell::new(notes.join(", ")).fg(cell_color)
 }
 
-fn get_departures_table(departures: Vec<TrainPrediction>) -> Table {
+fn get_departures_table(mut departures: Vec<TrainPrediction>) -> Table {
+    departures.sort_by(TrainPrediction::cmp_departure);
+
     let mut table = Table::new();
     table.set_header(vec!["Line", "Destination", "ETA", "Notes"]);
 
@@ -75,7 +84,11 @@ fn print_footer() {
     println!("Source: MetroHero API (https://www.dcmetrohero.com)")
 }
 
-pub fn print_plan(trip_info: TripInfo) {
+pub fn print_plan(mut trip_info: TripInfo) {
+    trip_info
+        .from_station_train_statuses
+        .sort_by(TrainPrediction::cmp_departure);
+
     // Print a summary of the ride.
     let trip_header = format!(
         "{} --> {}",
@@ -103,9 +116,28 @@ pub fn print_plan(trip_info: TripInfo) {
     println!("Departures from {}", trip_info.from_station_name);
     println!("{departures_table}");
 
+    // If elevators or escalators are out at either end of the trip, print them.
+    for (station_name, elevator_outages, escalator_outages) in [
+        (
+            &trip_info.from_station_name,
+            &trip_info.from_station_elevator_outages,
+            &trip_info.from_station_escalator_outages,
+        ),
+        (
+            &trip_info.to_station_name,
+            &trip_info.to_station_elevator_outages,
+            &trip_info.to_station_escalator_outages,
+        ),
+    ] {
+        let outages = get_outages(elevator_outages, escalator_outages);
+        if !outages.is_empty() {
+            println!("\nElevator and escalator outages at {station_name}");
+            println!("{}", get_outages_table(&outages));
+        }
+    }
+
     // If alerts, print them.
-    if trip_info.metro_alerts.is_some() {
-        let alerts = trip_info.metro_alerts.unwrap();
+    if let Some(alerts) = trip_info.metro_alerts {
         let mut alert_table = Table::new();
         alert_table.set_header(vec!["Date", "Description"]);
 
@@ -119,6 +151,67 @@ pub fn print_plan(trip_info: TripInfo) {
     }
 }
 
+/// Combine elevator and escalator outages, elevators first.
+fn get_outages<'a>(
+    elevator_outages: &'a Option<Vec<ElevatorEscalatorOutage>>,
+    escalator_outages: &'a Option<Vec<ElevatorEscalatorOutage>>,
+) -> Vec<&'a ElevatorEscalatorOutage> {
+    elevator_outages
+        .iter()
+        .chain(escalator_outages)
+        .flatten()
+        .collect()
+}
+
+fn get_outages_table(outages: &[&ElevatorEscalatorOutage]) -> Table {
+    let mut table = Table::new();
+    table.set_header(vec![
+        "Unit",
+        "Location",
+        "Symptom",
+        "Out since",
+        "Estimated return",
+    ]);
+
+    for outage in outages {
+        // Elevator outages can leave riders with mobility needs unable to use the station.
+        let mut unit_cell = Cell::new(&outage.unit_type);
+        if outage.unit_type.eq_ignore_ascii_case("ELEVATOR") {
+            unit_cell = unit_cell.fg(Color::Red).add_attribute(Attribute::Bold);
+        }
+        table.add_row(vec![
+            unit_cell,
+            Cell::new(&outage.location_description),
+            Cell::new(&outage.symptom_description),
+            Cell::new(&outage.out_of_service_date),
+            Cell::new(&outage.estimated_return_to_service_date),
+        ]);
+    }
+    table
+}
+
+/// Render elevator and escalator outages at a station.
+pub fn print_outages(
+    station: &StationCode,
+    elevator_outages: &Option<Vec<ElevatorEscalatorOutage>>,
+    escalator_outages: &Option<Vec<ElevatorEscalatorOutage>>,
+) {
+    let outages = get_outages(elevator_outages, escalator_outages);
+    if outages.is_empty() {
+        println!(
+            "No elevator or escalator outages reported at {} ({station}).",
+            station.to_name()
+        );
+    } else {
+        println!(
+            "Elevator and escalator outages at {} ({station})",
+            station.to_name()
+        );
+        println!("{}", get_outages_table(&outages));
+    }
+    print_footer();
+}
+
 pub fn print_departures(
     departure_station: &StationCode,
     departures: Vec<TrainPrediction>,
@@ -138,16 +231,425 @@ pub fn print_departures(
     print_footer();
 }
 
+/// Render a route planned offline, for when live trip information is unavailable.
+pub fn print_route(route: &Route) {
+    let from_station = route.legs[0].from_station();
+    let to_station = route.legs[route.legs.len() - 1].to_station();
+    let trip_header = format!("{} --> {}", from_station.to_name(), to_station.to_name());
+    println!("{}", trip_header.bold());
+    println!(
+        "Route:            {} stops, {} transfers\n",
+        route.num_stops(),
+        route.transfers().len()
+    );
+
+    let mut table = Table::new();
+    table.set_header(vec!["Line", "Board at", "Ride to", "Stops"]);
+    for leg in &route.legs {
+        let lines: Vec<String> = leg.lines.iter().map(|line| line.to_string()).collect();
+        table.add_row(vec![
+            Cell::new(lines.join("/"))
+                .fg(leg.lines[0].get_color())
+                .add_attribute(Attribute::Bold)
+                .set_alignment(CellAlignment::Center),
+            Cell::new(leg.from_station().to_name()),
+            Cell::new(leg.to_station().to_name()),
+            Cell::new(leg.num_stops()),
+        ]);
+    }
+    println!("{table}");
+    println!("Live trip information is unavailable; this route was planned offline.");
+}
+
+/// Format an optional number of minutes, e.g. a platform wait time.
+fn format_minutes(minutes: Option<f64>) -> String {
+    minutes.map_or(String::from("-"), |minutes| format!("{minutes:.1}m"))
+}
+
+/// Render a table of line health metrics, optionally for a single line.
+pub fn print_metrics(response: &SystemMetricsResponse, line_filter: Option<LineCode>) {
+    let mut table = Table::new();
+    table.set_header(vec![
+        "Line",
+        "Trains",
+        "8-car",
+        "Delayed",
+        "Wait (expected)",
+        "Headway adherence",
+        "Gaps",
+    ]);
+
+    let lines = response
+        .line_metrics_by_line
+        .iter()
+        .filter(|(line, _)| line_filter.is_none_or(|filter| filter == *line));
+    for (line, metrics) in lines {
+        let trains_color = if metrics.num_trains < metrics.expected_num_trains {
+            Color::Red
+        } else {
+            Color::White
+        };
+        let delayed_color = if metrics.num_delayed_trains > 0 {
+            Color::Yellow
+        } else {
+            Color::White
+        };
+        let headway_adherence = metrics
+            .average_headway_adherence
+            .map_or(String::from("-"), |adherence| format!("{adherence:.0}%"));
+
+        table.add_row(vec![
+            Cell::new(line)
+                .fg(line.get_color())
+                .add_attribute(Attribute::Bold)
+                .set_alignment(CellAlignment::Center),
+            Cell::new(format!(
+                "{}/{}",
+                metrics.num_trains, metrics.expected_num_trains
+            ))
+            .fg(trains_color),
+            Cell::new(metrics.num_eight_car_trains),
+            Cell::new(metrics.num_delayed_trains).fg(delayed_color),
+            Cell::new(format!(
+                "{} ({})",
+                format_minutes(metrics.average_platform_wait_time),
+                format_minutes(metrics.expected_platform_wait_time)
+            )),
+            Cell::new(headway_adherence),
+            Cell::new(metrics.service_gaps.len()),
+        ]);
+    }
+
+    println!("{}", "Metrorail Line Metrics".bold());
+    println!("{table}");
+    print_footer();
+}
+
+/// Service gaps across all lines, most severe first.
+///
+/// Gaps less than `threshold` minutes longer than scheduled are left out.
+pub(crate) fn service_gaps(response: &SystemMetricsResponse, threshold: f64) -> Vec<&ServiceGaps> {
+    let mut gaps: Vec<&ServiceGaps> = response
+        .line_metrics_by_line
+        .iter()
+        .flat_map(|(_, metrics)| &metrics.service_gaps)
+        .filter(|gap| gap.severity() >= threshold)
+        .collect();
+    gaps.sort_by(|a, b| b.severity().total_cmp(&a.severity()));
+    gaps
+}
+
+/// Render a table of service gaps across all lines, most severe first.
+///
+/// Gaps less than `threshold` minutes longer than scheduled are not shown.
+pub fn print_gaps(response: &SystemMetricsResponse, threshold: f64) {
+    let gaps = service_gaps(response, threshold);
+    if gaps.is_empty() {
+        println!("No service gaps reported.");
+        print_footer();
+        return;
+    }
+
+    let mut table = Table::new();
+    table.set_header(vec![
+        "Line",
+        "Direction",
+        "From",
+        "To",
+        "Trains",
+        "Gap (scheduled)",
+        "Excess",
+    ]);
+    for gap in gaps {
+        table.add_row(vec![
+            Cell::new(gap.line_code)
+                .fg(gap.line_code.get_color())
+                .add_attribute(Attribute::Bold)
+                .set_alignment(CellAlignment::Center),
+            Cell::new(gap.direction.as_str()),
+            Cell::new(gap.from_station_name.as_str()),
+            Cell::new(gap.to_station_name.as_str()),
+            Cell::new(format!("{} / {}", gap.from_train_id, gap.to_train_id)),
+            Cell::new(format!(
+                "{} ({})",
+                format_minutes(Some(gap.time_between_trains)),
+                format_minutes(Some(gap.scheduled_time_between_trains))
+            )),
+            Cell::new(format_minutes(Some(gap.severity()))).fg(Color::Red),
+        ]);
+    }
+
+    println!("{}", "Metrorail Service Gaps".bold());
+    println!("{table}");
+    print_footer();
+}
+
+/// Format how far behind schedule a train is, e.g. `+4m`.
+fn format_delay(seconds_off_schedule: i64) -> String {
+    if seconds_off_schedule < 60 {
+        String::from("On time")
+    } else {
+        format!("+{}m", seconds_off_schedule / 60)
+    }
+}
+
+fn get_train_status_cell(train: &TrainPrediction) -> Cell {
+    let mut notes: Vec<&str> = vec![];
+    let mut cell_color = Color::White;
+
+    if train.is_scheduled {
+        notes.push("Scheduled (Not Live)");
+        cell_color = Color::Grey;
+    }
+    if train.is_not_on_revenue_track {
+        notes.push("Off revenue track");
+        cell_color = Color::Grey;
+    }
+    if train.is_currently_holding_or_slow {
+        notes.push("Holding");
+        cell_color = Color::Yellow;
+    }
+    if train.is_keyed_down {
+        notes.push("Keyed down");
+        cell_color = Color::Red;
+    }
+
+    Cell::new(notes.join(", ")).fg(cell_color)
+}
+
+/// Sort trains by line and direction, or with the most delayed first if `by_delay` is set.
+pub(crate) fn sort_trains(trains: &mut [TrainPrediction], by_delay: bool) {
+    if by_delay {
+        trains.sort_by_key(|train| cmp::Reverse(train.seconds_off_schedule));
+    } else {
+        // Non-revenue trains are on no line, so are listed last.
+        trains.sort_by(|a, b| {
+            let line_order = |train: &TrainPrediction| {
+                LINES
+                    .iter()
+                    .position(|line| *line == train.line)
+                    .unwrap_or(usize::MAX)
+            };
+            line_order(a)
+                .cmp(&line_order(b))
+                .then(a.direction_number.cmp(&b.direction_number))
+                .then(a.train_id.cmp(&b.train_id))
+        });
+    }
+}
+
+/// Render a table of train positions.
+///
+/// Trains are listed by line and direction, or with the most delayed first if `by_delay` is set.
+pub fn print_trains(mut trains: Vec<TrainPrediction>, by_delay: bool) {
+    if trains.is_empty() {
+        println!("No trains found.");
+        print_footer();
+        return;
+    }
+    sort_trains(&mut trains, by_delay);
+
+    let mut table = Table::new();
+    table.set_header(vec![
+        "Train",
+        "Line",
+        "Location",
+        "Destination",
+        "Cars",
+        "Delay",
+        "Status",
+    ]);
+    for train in &trains {
+        let delay_color = if train.seconds_off_schedule >= 300 {
+            Color::Red
+        } else {
+            Color::White
+        };
+        table.add_row(vec![
+            Cell::new(train.train_id.as_str()),
+            Cell::new(train.line)
+                .fg(train.line.get_color())
+                .add_attribute(Attribute::Bold)
+                .set_alignment(CellAlignment::Center),
+            Cell::new(train.current_station_name.as_str()),
+            Cell::new(train.destination_name.as_str()),
+            Cell::new(train.car.as_str()).set_alignment(CellAlignment::Right),
+            Cell::new(format_delay(train.seconds_off_schedule)).fg(delay_color),
+            get_train_status_cell(train),
+        ]);
+    }
+
+    println!("{}", "Metrorail Train Positions".bold());
+    println!("{table}");
+    print_footer();
+}
+
+/// The types of rider reports made about a train, with their counts and whether they are
+/// positive, sorted by type.
+pub(crate) fn train_reports(train_tags: &TrainTags) -> Vec<(&'static str, i64, bool)> {
+    let tags = &train_tags.num_tags_by_type;
+    let mut reports: Vec<(&'static str, i64, bool)> = tags
+        .bad_tags()
+        .into_iter()
+        .map(|(tag, count)| (tag, count, false))
+        .chain(
+            tags.good_tags()
+                .into_iter()
+                .map(|(tag, count)| (tag, count, true)),
+        )
+        .filter(|(_, count, _)| *count > 0)
+        .collect();
+    reports.sort_by_key(|(tag, _, _)| *tag);
+    reports
+}
+
+/// Render a train's position, if it is in service, and rider reports about it.
+pub fn print_train(train_id: &str, train: Option<&TrainPrediction>, train_tags: &TrainTags) {
+    println!("{}", format!("Train {train_id}").bold());
+    match train {
+        Some(train) => {
+            let location = match &train.previous_station_name {
+                Some(previous) => format!("{} (from {previous})", train.current_station_name),
+                None => train.current_station_name.clone(),
+            };
+            let mut table = Table::new();
+            table.add_row(vec![
+                Cell::new("Line"),
+                Cell::new(train.line)
+                    .fg(train.line.get_color())
+                    .add_attribute(Attribute::Bold),
+            ]);
+            table.add_row(vec!["Destination", &train.destination_name]);
+            table.add_row(vec!["Location", &location]);
+            table.add_row(vec!["Cars", &train.car]);
+            table.add_row(vec![
+                Cell::new("Delay"),
+                Cell::new(format_delay(train.seconds_off_schedule)),
+            ]);
+            table.add_row(vec![Cell::new("Status"), get_train_status_cell(train)]);
+            table.add_row(vec![Cell::new("Observed"), Cell::new(&train.observed_date)]);
+            println!("{table}");
+        }
+        None => println!("Train {train_id} was not found among trains currently in service."),
+    }
+
+    let reports = train_reports(train_tags);
+    if reports.is_empty() {
+        println!("No rider reports about this train.");
+    } else {
+        let mut table = Table::new();
+        table.set_header(vec!["Report", "Count"]);
+        for (tag, count, is_positive) in reports {
+            let color = if is_positive {
+                Color::Green
+            } else {
+                Color::Red
+            };
+            table.add_row(vec![Cell::new(tag).fg(color), Cell::new(count)]);
+        }
+        println!("Rider reports");
+        println!("{table}");
+    }
+    print_footer();
+}
+
+/// Format the lines an item is about, e.g. `OR, SV`.
+fn format_lines(lines: &[LineCode]) -> String {
+    let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+    lines.join(", ")
+}
+
+/// Render a table of tweets.
+pub fn print_tweets(tweets: Vec<Tweet>) {
+    if tweets.is_empty() {
+        println!("No tweets found.");
+        print_footer();
+        return;
+    }
+
+    let mut table = Table::new();
+    table.set_header(vec!["Date", "Lines", "Tweet", "Link"]);
+    for tweet in tweets {
+        table.add_row(vec![
+            Cell::new(tweet.date),
+            Cell::new(format_lines(&tweet.line_codes)),
+            Cell::new(tweet.text),
+            Cell::new(tweet.url),
+        ]);
+    }
+
+    println!("{}", "Metrorail Tweets".bold());
+    println!("{table}");
+    print_footer();
+}
+
+/// Render a table of WMATA alerts.
+pub fn print_alerts(alerts: Vec<MetroAlert>) {
+    if alerts.is_empty() {
+        println!("No alerts reported.");
+        print_footer();
+        return;
+    }
+
+    let mut table = Table::new();
+    table.set_header(vec!["Date", "Lines", "Description"]);
+    for alert in alerts {
+        table.add_row(vec![
+            Cell::new(alert.date),
+            Cell::new(format_lines(&alert.line_codes)),
+            Cell::new(alert.description),
+        ]);
+    }
+
+    println!("{}", "WMATA Alerts".bold());
+    println!("{table}");
+    println!("More information: https://www.wmata.com");
+    print_footer();
+}
+
+/// Render a table of stations near a location, nearest first.
+pub fn print_nearby_stations(stations: &[NearbyStation]) {
+    let mut table = Table::new();
+    table.set_header(vec!["Code", "Name", "Lines", "Distance"]);
+    for station in stations {
+        let lines: Vec<String> = station
+            .info
+            .all_lines()
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        let distance = if station.distance_meters < 1_000.0 {
+            format!("{:.0} m", station.distance_meters)
+        } else {
+            format!("{:.1} km", station.distance_meters / 1_000.0)
+        };
+        table.add_row(vec![
+            Cell::new(format!("{}", station.info.code)),
+            Cell::new(station.info.name),
+            Cell::new(lines.join(", ")),
+            Cell::new(distance).set_alignment(CellAlignment::Right),
+        ]);
+    }
+
+    println!("{}", "Nearest Metrorail Stations".bold());
+    println!("{table}");
+}
+
 /// Render a table of Metrorail stations and their WMATA codes.
 pub fn print_stations() {
     let mut table = Table::new();
-    table.set_header(vec!["Code", "Name"]);
-    let stations = StationCode::iter().filter(|station| station != &StationCode::UNKNOWN);
+    table.set_header(vec!["Code", "Name", "Lines", "Location", "Parking"]);
+    let stations = StationCode::iter().filter_map(|station| station.info());
 
     for station in stations {
+        let lines: Vec<String> = station.lines.iter().map(|line| line.to_string()).collect();
+        let parking = if station.parking { "Yes" } else { "" };
         table.add_row(vec![
-            Cell::new(format!("{station}")),
-            Cell::new(station.to_name().to_string()),
+            Cell::new(format!("{}", station.code)),
+            Cell::new(station.name),
+            Cell::new(lines.join(", ")),
+            Cell::new(station.jurisdiction),
+            Cell::new(parking),
         ]);
     }
 
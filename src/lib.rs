@@ -9,7 +9,6 @@
 //! # use std::path::PathBuf;
 //! # use mockito::mock;
 //! #
-//! # #[test]
 //! # fn main() -> io::Result<()> {
 //! # let tripinfo_mock = mock("GET", "/metrorail/trips/K04/K05")
 //! #           .with_status(200)
@@ -47,19 +46,35 @@ extern crate lazy_static;
 use crate::cli::run_cli;
 
 // Re-export some important structs
+#[cfg(feature = "async")]
 #[doc(inline)]
-pub use client::MetroHeroClient;
+pub use async_client::AsyncMetroHeroClient;
+#[doc(inline)]
+pub use client::{MetroHeroClient, MetroHeroClientBuilder};
 #[doc(inline)]
 pub use errors::MetroHeroError;
 
 #[doc(inline)]
-pub use schemas::{LineCode, StationCode, TrainPrediction, TripInfo};
+pub use schemas::{Eta, LineCode, StationCode, TrainPrediction, TripInfo};
 
+#[cfg(feature = "async")]
+pub mod async_client;
+pub mod cache;
 mod cli;
 pub mod client;
+#[cfg(feature = "chrono")]
+pub mod datetime;
 pub mod errors;
 mod format;
+pub mod network;
+mod output;
+pub mod planner;
+pub mod ratelimit;
+pub mod retry;
 pub mod schemas;
+pub mod search;
+pub mod stations;
+pub mod transport;
 
 #[doc(hidden)]
 pub fn run() {
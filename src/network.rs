@@ -0,0 +1,318 @@
+#![warn(missing_docs)]
+//! A static model of the Metrorail network: the stations on each line, in order, and the
+//! stations where riders can transfer between lines.
+//!
+//! Lines are described in [`Direction::D1`] order, which is the order reported by the API as
+//! direction number 1, e.g. from Shady Grove towards Glenmont on the Red line.
+//!
+//! ```
+//! use metrohero_rs::{LineCode, StationCode};
+//! use metrohero_rs::network::{self, Direction};
+//!
+//! assert_eq!(network::terminal(LineCode::Red, Direction::D1), Some(StationCode::B11));
+//! assert_eq!(
+//!     network::next_station(StationCode::K04, LineCode::Orange, Direction::D2),
+//!     Some(StationCode::K05)
+//! );
+//! assert!(network::lines_serving(StationCode::C05).contains(&LineCode::Blue));
+//! ```
+use crate::schemas::{LineCode, StationCode};
+use StationCode::*;
+
+/// The direction a train is travelling along its line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    /// Direction number 1: towards Glenmont, New Carrollton, Downtown Largo, Mt Vernon Square or
+    /// Greenbelt.
+    D1,
+    /// Direction number 2: towards Shady Grove, Vienna, Ashburn, Franconia-Springfield,
+    /// Huntington or Branch Avenue.
+    D2,
+}
+
+impl Direction {
+    /// Get the direction for a direction number reported by the API.
+    pub fn from_number(direction_number: i64) -> Option<Direction> {
+        match direction_number {
+            1 => Some(Direction::D1),
+            2 => Some(Direction::D2),
+            _ => None,
+        }
+    }
+
+    /// The direction number the API reports for this direction.
+    pub fn number(&self) -> i64 {
+        match self {
+            Direction::D1 => 1,
+            Direction::D2 => 2,
+        }
+    }
+
+    /// The opposite direction.
+    pub fn reverse(&self) -> Direction {
+        match self {
+            Direction::D1 => Direction::D2,
+            Direction::D2 => Direction::D1,
+        }
+    }
+}
+
+/// Every revenue line, in the order used by the API's system metrics.
+pub const LINES: [LineCode; 6] = [
+    LineCode::Red,
+    LineCode::Orange,
+    LineCode::Silver,
+    LineCode::Blue,
+    LineCode::Yellow,
+    LineCode::Green,
+];
+
+const RED: [StationCode; 27] = [
+    A15, A14, A13, A12, A11, A10, A09, A08, A07, A06, A05, A04, A03, A02, A01, B01, B02, B03, B35,
+    B04, B05, B06, B07, B08, B09, B10, B11,
+];
+
+const ORANGE: [StationCode; 26] = [
+    K08, K07, K06, K05, K04, K03, K02, K01, C05, C04, C03, C02, C01, D01, D02, D03, D04, D05, D06,
+    D07, D08, D09, D10, D11, D12, D13,
+];
+
+const SILVER: [StationCode; 34] = [
+    N12, N11, N10, N09, N08, N07, N06, N04, N03, N02, N01, K05, K04, K03, K02, K01, C05, C04, C03,
+    C02, C01, D01, D02, D03, D04, D05, D06, D07, D08, G01, G02, G03, G04, G05,
+];
+
+const BLUE: [StationCode; 28] = [
+    J03, J02, C13, C12, C11, C10, C09, C08, C07, C06, C05, C04, C03, C02, C01, D01, D02, D03, D04,
+    D05, D06, D07, D08, G01, G02, G03, G04, G05,
+];
+
+const YELLOW: [StationCode; 13] = [
+    C15, C14, C13, C12, C11, C10, C09, C08, C07, F03, F02, F01, E01,
+];
+
+const GREEN: [StationCode; 21] = [
+    F11, F10, F09, F08, F07, F06, F05, F04, F03, F02, F01, E01, E02, E03, E04, E05, E06, E07, E08,
+    E09, E10,
+];
+
+/// Stations with separate platforms, and station codes, for different lines.
+const STATION_COMPLEXES: [(StationCode, StationCode); 4] =
+    [(A01, C01), (B01, F01), (B06, E06), (D03, F03)];
+
+/// The stations on a line, in [`Direction::D1`] order.
+///
+/// Non-revenue trains have no fixed route, so [`LineCode::NonRevenue`] has no stations.
+pub fn stations_on(line: LineCode) -> &'static [StationCode] {
+    match line {
+        LineCode::Red => &RED,
+        LineCode::Orange => &ORANGE,
+        LineCode::Silver => &SILVER,
+        LineCode::Blue => &BLUE,
+        LineCode::Yellow => &YELLOW,
+        LineCode::Green => &GREEN,
+        LineCode::NonRevenue => &[],
+    }
+}
+
+/// The stations on a line, in the order trains travelling in `direction` reach them.
+pub fn stations_towards(line: LineCode, direction: Direction) -> Vec<StationCode> {
+    let mut stations = stations_on(line).to_vec();
+    if direction == Direction::D2 {
+        stations.reverse();
+    }
+    stations
+}
+
+/// The lines which stop at a station.
+///
+/// Only lines stopping at this station code are included. See [`other_platform`] for stations
+/// whose lines stop at separate platforms.
+pub fn lines_serving(station: StationCode) -> Vec<LineCode> {
+    LINES
+        .into_iter()
+        .filter(|line| stations_on(*line).contains(&station))
+        .collect()
+}
+
+/// The station a train on `line`, travelling in `direction`, reaches after `station`.
+///
+/// Returns `None` if the line does not stop at `station`, or `station` is the line's terminal.
+pub fn next_station(
+    station: StationCode,
+    line: LineCode,
+    direction: Direction,
+) -> Option<StationCode> {
+    let stations = stations_on(line);
+    let index = stations.iter().position(|s| *s == station)?;
+    match direction {
+        Direction::D1 => stations.get(index + 1).copied(),
+        Direction::D2 => index.checked_sub(1).map(|i| stations[i]),
+    }
+}
+
+/// The last station reached by trains on `line` travelling in `direction`.
+pub fn terminal(line: LineCode, direction: Direction) -> Option<StationCode> {
+    match direction {
+        Direction::D1 => stations_on(line).last().copied(),
+        Direction::D2 => stations_on(line).first().copied(),
+    }
+}
+
+/// Whether a station is the terminal of any line.
+pub fn is_terminal(station: StationCode) -> bool {
+    LINES.into_iter().any(|line| {
+        terminal(line, Direction::D1) == Some(station)
+            || terminal(line, Direction::D2) == Some(station)
+    })
+}
+
+/// The code for the other platform of a station with separate platforms for different lines,
+/// e.g. [`StationCode::C01`] for Metro Center's Red line platform, [`StationCode::A01`].
+pub fn other_platform(station: StationCode) -> Option<StationCode> {
+    STATION_COMPLEXES.iter().find_map(|(a, b)| match station {
+        s if s == *a => Some(*b),
+        s if s == *b => Some(*a),
+        _ => None,
+    })
+}
+
+/// Whether riders can change between lines with different routes at a station.
+///
+/// Stations where lines share all their stops, e.g. Court House on the Orange and Silver lines,
+/// are not transfer stations.
+pub fn is_transfer_station(station: StationCode) -> bool {
+    if other_platform(station).is_some() {
+        return true;
+    }
+
+    // Lines diverge at a station if their stations next to it differ.
+    let lines = lines_serving(station);
+    lines.iter().any(|a| {
+        lines.iter().any(|b| {
+            [Direction::D1, Direction::D2].into_iter().any(|direction| {
+                next_station(station, *a, direction) != next_station(station, *b, direction)
+            })
+        })
+    })
+}
+
+/// All transfer stations, in the order of [`StationCode`].
+pub fn transfer_stations() -> Vec<StationCode> {
+    use strum::IntoEnumIterator;
+    StationCode::iter()
+        .filter(|station| is_transfer_station(*station))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::network::{
+        is_terminal, is_transfer_station, lines_serving, next_station, other_platform, stations_on,
+        stations_towards, terminal, transfer_stations, Direction, LINES,
+    };
+    use crate::schemas::tests::get_test_data;
+    use crate::schemas::{LineCode, StationCode, TripInfo};
+    use rstest::rstest;
+    use std::collections::HashSet;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_every_station_is_on_a_line() {
+        for station in StationCode::iter().filter(|s| *s != StationCode::UNKNOWN) {
+            assert!(
+                !lines_serving(station).is_empty(),
+                "{station} is on no line"
+            );
+        }
+    }
+
+    #[test]
+    fn test_lines_have_no_repeated_stations() {
+        for line in LINES {
+            let unique: HashSet<_> = stations_on(line).iter().collect();
+            assert_eq!(unique.len(), stations_on(line).len(), "{line}");
+        }
+    }
+
+    #[rstest]
+    #[case::red(LineCode::Red, StationCode::B11, StationCode::A15)]
+    #[case::orange(LineCode::Orange, StationCode::D13, StationCode::K08)]
+    #[case::silver(LineCode::Silver, StationCode::G05, StationCode::N12)]
+    #[case::blue(LineCode::Blue, StationCode::G05, StationCode::J03)]
+    #[case::yellow(LineCode::Yellow, StationCode::E01, StationCode::C15)]
+    #[case::green(LineCode::Green, StationCode::E10, StationCode::F11)]
+    fn test_terminals(#[case] line: LineCode, #[case] d1: StationCode, #[case] d2: StationCode) {
+        assert_eq!(terminal(line, Direction::D1), Some(d1));
+        assert_eq!(terminal(line, Direction::D2), Some(d2));
+        assert!(is_terminal(d1) && is_terminal(d2));
+        assert_eq!(next_station(d1, line, Direction::D1), None);
+    }
+
+    #[test]
+    fn test_next_station() {
+        assert_eq!(
+            next_station(StationCode::N01, LineCode::Silver, Direction::D1),
+            Some(StationCode::K05)
+        );
+        assert_eq!(
+            next_station(StationCode::B03, LineCode::Red, Direction::D1),
+            Some(StationCode::B35)
+        );
+        assert_eq!(
+            next_station(StationCode::N01, LineCode::Orange, Direction::D1),
+            None
+        );
+    }
+
+    #[test]
+    fn test_transfer_stations() {
+        assert_eq!(
+            transfer_stations(),
+            vec![
+                StationCode::A01,
+                StationCode::B01,
+                StationCode::B06,
+                StationCode::C01,
+                StationCode::C05,
+                StationCode::C07,
+                StationCode::C13,
+                StationCode::D03,
+                StationCode::D08,
+                StationCode::E01,
+                StationCode::E06,
+                StationCode::F01,
+                StationCode::F03,
+                StationCode::K05,
+            ]
+        );
+        assert!(!is_transfer_station(StationCode::K01));
+        assert_eq!(other_platform(StationCode::F03), Some(StationCode::D03));
+    }
+
+    /// Check the network against the routes reported by the API.
+    #[rstest]
+    #[case::from_api("tripinfo_api.json")]
+    #[case::from_api2("tripinfo_api2.json")]
+    #[case::from_api_na("tripinfo_api_na_trains.json")]
+    #[case::from_docs("tripinfo_docs.json")]
+    fn test_trip_station_codes(#[case] filename: String) {
+        let trip_info: TripInfo = serde_json::from_str(&get_test_data(filename)).unwrap();
+        for line in &trip_info.line_codes {
+            let route: Vec<StationCode> = [Direction::D1, Direction::D2]
+                .into_iter()
+                .map(|direction| stations_towards(*line, direction))
+                .find_map(|stations| {
+                    let start = stations
+                        .iter()
+                        .position(|s| *s == trip_info.from_station_code)?;
+                    let end = stations
+                        .iter()
+                        .position(|s| *s == trip_info.to_station_code)?;
+                    (start <= end).then(|| stations[start..=end].to_vec())
+                })
+                .unwrap();
+            assert_eq!(route, trip_info.trip_station_codes, "{line}");
+        }
+    }
+}
@@ -0,0 +1,835 @@
+#![warn(missing_docs)]
+//! Machine-readable output for the MetroHero CLI.
+//!
+//! JSON, NDJSON and YAML output holds the items a command fetched, such as [`TripInfo`]s or
+//! [`Tweet`]s, with the same structure and field names as the MetroHero API returns them. Station
+//! metadata and rider reports, which have no API equivalent, are written as [`StationInfo`],
+//! [`NearbyStation`] and [`TrainDetails`].
+//!
+//! CSV can't hold nested values, so each item is flattened into one of the `*Record` types
+//! below first. Lists within a record, such as the lines serving a station, are written as a
+//! single string separated by `;`, and missing values are written as empty fields. Timestamps are
+//! written as reported by the API in every format, whichever features the crate is built with.
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::format;
+use crate::schemas::{
+    ElevatorEscalatorOutage, LineCode, LineMetrics, MetroAlert, ServiceGaps, StationCode,
+    TrainPrediction, TrainTags, TripInfo, Tweet,
+};
+use crate::stations::{NearbyStation, StationInfo};
+
+/// A flattened item which can be written as a row of CSV.
+pub trait CsvRecord: Serialize {
+    /// The CSV column names, in the order the record's fields are serialized.
+    const HEADER: &'static [&'static str];
+}
+
+/// The formats the CLI can print its output in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable tables.
+    Table,
+    /// A JSON array of records.
+    Json,
+    /// One JSON record per line.
+    Ndjson,
+    /// CSV with a header row.
+    Csv,
+    /// A YAML sequence of records.
+    Yaml,
+}
+
+/// Write items in a machine-readable format.
+///
+/// Items are written as they are, except in CSV, where each is first flattened into a record with
+/// `to_record`. The CSV header is written even when there are no items. Tables are rendered by the
+/// `format` module, so [`OutputFormat::Table`] is rejected with [`io::ErrorKind::InvalidInput`].
+pub fn write_items<W, T, R, F>(
+    mut writer: W,
+    format: OutputFormat,
+    items: &[T],
+    to_record: F,
+) -> io::Result<()>
+where
+    W: Write,
+    T: Serialize,
+    R: CsvRecord,
+    F: Fn(&T) -> R,
+{
+    match format {
+        OutputFormat::Table => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "tables are not a machine-readable format",
+            ))
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut writer, items)?;
+            writeln!(writer)?;
+        }
+        OutputFormat::Ndjson => {
+            for item in items {
+                serde_json::to_writer(&mut writer, item)?;
+                writeln!(writer)?;
+            }
+        }
+        OutputFormat::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(&mut writer);
+            // The header is otherwise written with the first record.
+            if items.is_empty() {
+                csv_writer.write_record(R::HEADER)?;
+            }
+            for item in items {
+                csv_writer.serialize(to_record(item))?;
+            }
+            csv_writer.flush()?;
+        }
+        OutputFormat::Yaml => {
+            serde_yaml::to_writer(&mut writer, items).map_err(io::Error::other)?;
+        }
+    }
+    writer.flush()
+}
+
+/// A train's position, if it is in service, and rider reports about it, emitted by `train`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrainDetails<'a> {
+    /// The train's ID.
+    pub train_id: &'a str,
+    /// The train's position, or `None` if it was not found among trains currently in service.
+    pub position: Option<&'a TrainPrediction>,
+    /// Rider reports about the train.
+    pub tags: &'a TrainTags,
+}
+
+/// Join a list of values into a single field.
+fn join<T: ToString>(values: &[T]) -> String {
+    let values: Vec<String> = values.iter().map(T::to_string).collect();
+    values.join(";")
+}
+
+/// A summary of a trip, written as CSV by `plan`.
+#[derive(Debug, Serialize)]
+pub struct TripRecord {
+    /// The code of the station the trip starts at.
+    pub from_station_code: StationCode,
+    /// The name of the station the trip starts at.
+    pub from_station_name: String,
+    /// The code of the station the trip ends at.
+    pub to_station_code: StationCode,
+    /// The name of the station the trip ends at.
+    pub to_station_name: String,
+    /// The ride time predicted from current conditions, in minutes.
+    pub predicted_ride_minutes: f64,
+    /// The ride time under normal conditions, in minutes.
+    pub expected_ride_minutes: f64,
+    /// ETAs of the next trains from the origin, soonest first, e.g. `BRD;5m`.
+    pub next_trains: String,
+    /// The number of WMATA alerts affecting the trip.
+    pub num_alerts: usize,
+    /// The number of elevators and escalators out of service at either end of the trip.
+    pub num_outages: usize,
+}
+
+impl CsvRecord for TripRecord {
+    const HEADER: &'static [&'static str] = &[
+        "from_station_code",
+        "from_station_name",
+        "to_station_code",
+        "to_station_name",
+        "predicted_ride_minutes",
+        "expected_ride_minutes",
+        "next_trains",
+        "num_alerts",
+        "num_outages",
+    ];
+}
+
+impl From<&TripInfo> for TripRecord {
+    fn from(trip_info: &TripInfo) -> Self {
+        let mut departures: Vec<_> = trip_info.from_station_train_statuses.iter().collect();
+        departures.sort_by(|a, b| a.cmp_departure(b));
+        let etas: Vec<_> = departures.into_iter().map(TrainPrediction::eta).collect();
+        let num_outages = [
+            &trip_info.from_station_elevator_outages,
+            &trip_info.from_station_escalator_outages,
+            &trip_info.to_station_elevator_outages,
+            &trip_info.to_station_escalator_outages,
+        ]
+        .into_iter()
+        .flatten()
+        .map(Vec::len)
+        .sum();
+        TripRecord {
+            from_station_code: trip_info.from_station_code,
+            from_station_name: trip_info.from_station_name.clone(),
+            to_station_code: trip_info.to_station_code,
+            to_station_name: trip_info.to_station_name.clone(),
+            predicted_ride_minutes: trip_info.predicted_ride_time,
+            expected_ride_minutes: trip_info.expected_ride_time,
+            next_trains: join(&etas),
+            num_alerts: trip_info.metro_alerts.as_ref().map_or(0, Vec::len),
+            num_outages,
+        }
+    }
+}
+
+/// A train departing a station, written as CSV by `departures`.
+#[derive(Debug, Serialize)]
+pub struct DepartureRecord {
+    /// The line the train is running on.
+    pub line: LineCode,
+    /// The train's destination.
+    pub destination: String,
+    /// The train's ETA, e.g. `5m`, `ARR` or `BRD`.
+    pub eta: String,
+    /// The estimated number of minutes until the train arrives, if known.
+    pub minutes_away: Option<f64>,
+    /// The number of cars, if known.
+    pub cars: String,
+    /// Whether the ETA comes from the schedule rather than a live train.
+    pub is_scheduled: bool,
+    /// Whether the train is currently holding or moving slowly.
+    pub is_holding: bool,
+}
+
+impl CsvRecord for DepartureRecord {
+    const HEADER: &'static [&'static str] = &[
+        "line",
+        "destination",
+        "eta",
+        "minutes_away",
+        "cars",
+        "is_scheduled",
+        "is_holding",
+    ];
+}
+
+impl From<&TrainPrediction> for DepartureRecord {
+    fn from(train: &TrainPrediction) -> Self {
+        DepartureRecord {
+            line: train.line,
+            destination: train.destination.clone(),
+            eta: train.eta().to_string(),
+            minutes_away: train.minutes_away,
+            cars: train.car.clone(),
+            is_scheduled: train.is_scheduled,
+            is_holding: train.is_currently_holding_or_slow,
+        }
+    }
+}
+
+/// An elevator or escalator out of service, written as CSV by `outages`.
+#[derive(Debug, Serialize)]
+pub struct OutageRecord {
+    /// The code of the station the unit is at.
+    pub station_code: StationCode,
+    /// WMATA's identifier for the unit.
+    pub unit_name: String,
+    /// `ELEVATOR` or `ESCALATOR`.
+    pub unit_type: String,
+    /// Where the unit is in the station.
+    pub location: String,
+    /// Why the unit is out of service.
+    pub symptom: String,
+    /// When the unit went out of service.
+    pub out_of_service_date: String,
+    /// When the unit is expected to return to service.
+    pub estimated_return_to_service_date: String,
+}
+
+impl CsvRecord for OutageRecord {
+    const HEADER: &'static [&'static str] = &[
+        "station_code",
+        "unit_name",
+        "unit_type",
+        "location",
+        "symptom",
+        "out_of_service_date",
+        "estimated_return_to_service_date",
+    ];
+}
+
+impl From<&ElevatorEscalatorOutage> for OutageRecord {
+    fn from(outage: &ElevatorEscalatorOutage) -> Self {
+        OutageRecord {
+            station_code: outage.station_code,
+            unit_name: outage.unit_name.clone(),
+            unit_type: outage.unit_type.clone(),
+            location: outage.location_description.clone(),
+            symptom: outage.symptom_description.clone(),
+            out_of_service_date: outage.out_of_service_date.clone(),
+            estimated_return_to_service_date: outage.estimated_return_to_service_date.clone(),
+        }
+    }
+}
+
+/// A Metrorail station, written as CSV by `stations`.
+#[derive(Debug, Serialize)]
+pub struct StationRecord {
+    /// The station's RTU code.
+    pub code: StationCode,
+    /// The station's name.
+    pub name: &'static str,
+    /// The lines stopping at the station, e.g. `OR;SV;BL`.
+    pub lines: String,
+    /// `DC`, `MD` or `VA`.
+    pub jurisdiction: String,
+    /// The station's latitude, in degrees.
+    pub latitude: f64,
+    /// The station's longitude, in degrees.
+    pub longitude: f64,
+    /// Whether Metro operates parking at the station.
+    pub parking: bool,
+}
+
+impl CsvRecord for StationRecord {
+    const HEADER: &'static [&'static str] = &[
+        "code",
+        "name",
+        "lines",
+        "jurisdiction",
+        "latitude",
+        "longitude",
+        "parking",
+    ];
+}
+
+impl From<&StationInfo> for StationRecord {
+    fn from(info: &StationInfo) -> Self {
+        StationRecord {
+            code: info.code,
+            name: info.name,
+            lines: join(&info.lines),
+            jurisdiction: info.jurisdiction.to_string(),
+            latitude: info.latitude,
+            longitude: info.longitude,
+            parking: info.parking,
+        }
+    }
+}
+
+/// A station near a location, written as CSV by `near`.
+#[derive(Debug, Serialize)]
+pub struct NearbyStationRecord {
+    /// The station's RTU code.
+    pub code: StationCode,
+    /// The station's name.
+    pub name: &'static str,
+    /// The lines stopping at the station, e.g. `OR;SV;BL`.
+    pub lines: String,
+    /// The straight-line distance to the station, in meters.
+    pub distance_meters: f64,
+}
+
+impl CsvRecord for NearbyStationRecord {
+    const HEADER: &'static [&'static str] = &["code", "name", "lines", "distance_meters"];
+}
+
+impl From<&NearbyStation> for NearbyStationRecord {
+    fn from(station: &NearbyStation) -> Self {
+        NearbyStationRecord {
+            code: station.info.code,
+            name: station.info.name,
+            lines: join(&station.info.all_lines()),
+            distance_meters: station.distance_meters,
+        }
+    }
+}
+
+/// Health metrics for a line, written as CSV by `metrics`.
+#[derive(Debug, Serialize)]
+pub struct LineMetricsRecord {
+    /// The line.
+    pub line: LineCode,
+    /// The number of trains in service.
+    pub num_trains: i64,
+    /// The number of trains scheduled to be in service.
+    pub expected_num_trains: i64,
+    /// The number of 8-car trains in service.
+    pub num_eight_car_trains: i64,
+    /// The number of delayed trains.
+    pub num_delayed_trains: i64,
+    /// The average wait for a train on a platform, in minutes.
+    pub average_platform_wait_time: Option<f64>,
+    /// The scheduled average wait for a train on a platform, in minutes.
+    pub expected_platform_wait_time: Option<f64>,
+    /// The percentage of trains keeping to their scheduled headways.
+    pub average_headway_adherence: Option<f64>,
+    /// The number of current service gaps.
+    pub num_service_gaps: usize,
+}
+
+impl CsvRecord for LineMetricsRecord {
+    const HEADER: &'static [&'static str] = &[
+        "line",
+        "num_trains",
+        "expected_num_trains",
+        "num_eight_car_trains",
+        "num_delayed_trains",
+        "average_platform_wait_time",
+        "expected_platform_wait_time",
+        "average_headway_adherence",
+        "num_service_gaps",
+    ];
+}
+
+impl From<&LineMetrics> for LineMetricsRecord {
+    fn from(metrics: &LineMetrics) -> Self {
+        LineMetricsRecord {
+            line: metrics.line_code,
+            num_trains: metrics.num_trains,
+            expected_num_trains: metrics.expected_num_trains,
+            num_eight_car_trains: metrics.num_eight_car_trains,
+            num_delayed_trains: metrics.num_delayed_trains,
+            average_platform_wait_time: metrics.average_platform_wait_time,
+            expected_platform_wait_time: metrics.expected_platform_wait_time,
+            average_headway_adherence: metrics.average_headway_adherence,
+            num_service_gaps: metrics.service_gaps.len(),
+        }
+    }
+}
+
+/// A gap in service between two trains, written as CSV by `gaps`.
+#[derive(Debug, Serialize)]
+pub struct ServiceGapRecord {
+    /// The line.
+    pub line: LineCode,
+    /// The direction of travel, e.g. `Eastbound`.
+    pub direction: String,
+    /// The code of the station where the gap starts.
+    pub from_station_code: StationCode,
+    /// The name of the station where the gap starts.
+    pub from_station_name: String,
+    /// The code of the station where the gap ends.
+    pub to_station_code: StationCode,
+    /// The name of the station where the gap ends.
+    pub to_station_name: String,
+    /// The ID of the train ahead of the gap.
+    pub from_train_id: String,
+    /// The ID of the train behind the gap.
+    pub to_train_id: String,
+    /// The time between the trains, in minutes.
+    pub time_between_trains: f64,
+    /// The scheduled time between trains, in minutes.
+    pub scheduled_time_between_trains: f64,
+    /// How many minutes longer than scheduled the gap is.
+    pub severity: f64,
+}
+
+impl CsvRecord for ServiceGapRecord {
+    const HEADER: &'static [&'static str] = &[
+        "line",
+        "direction",
+        "from_station_code",
+        "from_station_name",
+        "to_station_code",
+        "to_station_name",
+        "from_train_id",
+        "to_train_id",
+        "time_between_trains",
+        "scheduled_time_between_trains",
+        "severity",
+    ];
+}
+
+impl From<&ServiceGaps> for ServiceGapRecord {
+    fn from(gap: &ServiceGaps) -> Self {
+        ServiceGapRecord {
+            line: gap.line_code,
+            direction: gap.direction.clone(),
+            from_station_code: gap.from_station_code,
+            from_station_name: gap.from_station_name.clone(),
+            to_station_code: gap.to_station_code,
+            to_station_name: gap.to_station_name.clone(),
+            from_train_id: gap.from_train_id.clone(),
+            to_train_id: gap.to_train_id.clone(),
+            time_between_trains: gap.time_between_trains,
+            scheduled_time_between_trains: gap.scheduled_time_between_trains,
+            severity: gap.severity(),
+        }
+    }
+}
+
+/// A tweet about Metrorail, written as CSV by `tweets`.
+#[derive(Debug, Serialize)]
+pub struct TweetRecord {
+    /// When the tweet was posted.
+    pub date: String,
+    /// The lines the tweet is about, e.g. `OR;SV`.
+    pub lines: String,
+    /// The stations the tweet is about, e.g. `K03;K04`.
+    pub stations: String,
+    /// Keywords MetroHero found in the tweet.
+    pub keywords: String,
+    /// The text of the tweet.
+    pub text: String,
+    /// A link to the tweet.
+    pub url: String,
+}
+
+impl CsvRecord for TweetRecord {
+    const HEADER: &'static [&'static str] =
+        &["date", "lines", "stations", "keywords", "text", "url"];
+}
+
+impl From<&Tweet> for TweetRecord {
+    fn from(tweet: &Tweet) -> Self {
+        TweetRecord {
+            date: tweet.date.clone(),
+            lines: join(&tweet.line_codes),
+            stations: join(&tweet.station_codes),
+            keywords: join(&tweet.keywords),
+            text: tweet.text.clone(),
+            url: tweet.url.clone(),
+        }
+    }
+}
+
+/// An alert issued by WMATA, written as CSV by `alerts`.
+#[derive(Debug, Serialize)]
+pub struct AlertRecord {
+    /// When the alert was issued.
+    pub date: String,
+    /// The lines the alert is about, e.g. `OR;SV`.
+    pub lines: String,
+    /// The stations the alert is about, e.g. `D06;D07`.
+    pub stations: String,
+    /// Keywords MetroHero found in the alert.
+    pub keywords: String,
+    /// The text of the alert.
+    pub description: String,
+}
+
+impl CsvRecord for AlertRecord {
+    const HEADER: &'static [&'static str] =
+        &["date", "lines", "stations", "keywords", "description"];
+}
+
+impl From<&MetroAlert> for AlertRecord {
+    fn from(alert: &MetroAlert) -> Self {
+        AlertRecord {
+            date: alert.date.clone(),
+            lines: join(&alert.line_codes),
+            stations: join(&alert.station_codes),
+            keywords: join(&alert.keywords),
+            description: alert.description.clone(),
+        }
+    }
+}
+
+/// The position of a train, written as CSV by `trains`.
+#[derive(Debug, Serialize)]
+pub struct TrainRecord {
+    /// The train's ID.
+    pub train_id: String,
+    /// The line the train is running on.
+    pub line: LineCode,
+    /// The direction number of the train, 1 or 2.
+    pub direction_number: i64,
+    /// The code of the station the train is at or approaching.
+    pub current_station_code: StationCode,
+    /// The name of the station the train is at or approaching.
+    pub current_station_name: String,
+    /// The train's destination.
+    pub destination: String,
+    /// The number of cars, if known.
+    pub cars: String,
+    /// How far behind schedule the train is, in seconds.
+    pub seconds_off_schedule: i64,
+    /// Whether the train is currently holding or moving slowly.
+    pub is_holding: bool,
+    /// Whether the train is keyed down.
+    pub is_keyed_down: bool,
+    /// Whether the train is off revenue track, e.g. in a yard.
+    pub is_not_on_revenue_track: bool,
+    /// When the train was observed at its position.
+    pub observed_date: String,
+}
+
+impl CsvRecord for TrainRecord {
+    const HEADER: &'static [&'static str] = &[
+        "train_id",
+        "line",
+        "direction_number",
+        "current_station_code",
+        "current_station_name",
+        "destination",
+        "cars",
+        "seconds_off_schedule",
+        "is_holding",
+        "is_keyed_down",
+        "is_not_on_revenue_track",
+        "observed_date",
+    ];
+}
+
+impl From<&TrainPrediction> for TrainRecord {
+    fn from(train: &TrainPrediction) -> Self {
+        TrainRecord {
+            train_id: train.train_id.clone(),
+            line: train.line,
+            direction_number: train.direction_number,
+            current_station_code: train.current_station_code,
+            current_station_name: train.current_station_name.clone(),
+            destination: train.destination_name.clone(),
+            cars: train.car.clone(),
+            seconds_off_schedule: train.seconds_off_schedule,
+            is_holding: train.is_currently_holding_or_slow,
+            is_keyed_down: train.is_keyed_down,
+            is_not_on_revenue_track: train.is_not_on_revenue_track,
+            observed_date: train.observed_date.clone(),
+        }
+    }
+}
+
+/// A train's position, if it is in service, and rider reports about it, written as CSV by
+/// `train`.
+#[derive(Debug, Serialize)]
+pub struct TrainDetailRecord {
+    /// The train's ID.
+    pub train_id: String,
+    /// Whether the train was found among trains currently in service. If not, its position
+    /// fields are missing.
+    pub in_service: bool,
+    /// The line the train is running on.
+    pub line: Option<LineCode>,
+    /// The name of the station the train is at or approaching.
+    pub current_station_name: Option<String>,
+    /// The train's destination.
+    pub destination: Option<String>,
+    /// The number of cars, if known.
+    pub cars: Option<String>,
+    /// How far behind schedule the train is, in seconds.
+    pub seconds_off_schedule: Option<i64>,
+    /// The number of positive rider reports.
+    pub num_positive_reports: i64,
+    /// The number of negative rider reports.
+    pub num_negative_reports: i64,
+    /// Counts of each type of report made, e.g. `CROWDED=3;GOOD_RIDE=1`.
+    pub reports: String,
+}
+
+impl CsvRecord for TrainDetailRecord {
+    const HEADER: &'static [&'static str] = &[
+        "train_id",
+        "in_service",
+        "line",
+        "current_station_name",
+        "destination",
+        "cars",
+        "seconds_off_schedule",
+        "num_positive_reports",
+        "num_negative_reports",
+        "reports",
+    ];
+}
+
+impl From<&TrainDetails<'_>> for TrainDetailRecord {
+    fn from(details: &TrainDetails) -> Self {
+        let (train_id, train, train_tags) = (details.train_id, details.position, details.tags);
+        let reports: Vec<String> = format::train_reports(train_tags)
+            .into_iter()
+            .map(|(tag, count, _)| format!("{tag}={count}"))
+            .collect();
+        TrainDetailRecord {
+            train_id: train_id.to_string(),
+            in_service: train.is_some(),
+            line: train.map(|train| train.line),
+            current_station_name: train.map(|train| train.current_station_name.clone()),
+            destination: train.map(|train| train.destination_name.clone()),
+            cars: train.map(|train| train.car.clone()),
+            seconds_off_schedule: train.map(|train| train.seconds_off_schedule),
+            num_positive_reports: train_tags.num_positive_tags,
+            num_negative_reports: train_tags.num_negative_tags,
+            reports: reports.join(";"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::output::{
+        write_items, AlertRecord, CsvRecord, DepartureRecord, LineMetricsRecord,
+        NearbyStationRecord, OutageRecord, OutputFormat, ServiceGapRecord, StationRecord,
+        TrainDetailRecord, TrainDetails, TrainRecord, TripRecord, TweetRecord,
+    };
+    use crate::schemas::tests::get_test_data;
+    use crate::schemas::{
+        LineCode, ServiceGaps, StationCode, SystemMetricsResponse, TrainTags, TripInfo,
+    };
+    use crate::stations::StationInfo;
+    use rstest::rstest;
+
+    fn stations() -> Vec<&'static StationInfo> {
+        [StationCode::K04, StationCode::C01]
+            .iter()
+            .map(|station| station.info().unwrap())
+            .collect()
+    }
+
+    fn write(format: OutputFormat) -> String {
+        let mut buffer = vec![];
+        write_items(&mut buffer, format, &stations(), |info| {
+            StationRecord::from(*info)
+        })
+        .unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_write_json() {
+        let output: serde_json::Value = serde_json::from_str(&write(OutputFormat::Json)).unwrap();
+        assert_eq!(output[0]["code"], "K04");
+        assert_eq!(output[1]["lines"], serde_json::json!(["OR", "SV", "BL"]));
+        assert_eq!(output[1]["otherPlatform"], "A01");
+        assert_eq!(output[1]["parking"], false);
+    }
+
+    #[test]
+    fn test_write_ndjson() {
+        let output = write(OutputFormat::Ndjson);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let record: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(record["name"], "Ballston-MU");
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let output = write(OutputFormat::Csv);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[0],
+            "code,name,lines,jurisdiction,latitude,longitude,parking"
+        );
+        assert!(lines[1].starts_with("K04,Ballston-MU,OR;SV,VA,"));
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn test_write_yaml() {
+        let output = write(OutputFormat::Yaml);
+        assert!(output.starts_with("- code: K04\n"));
+        assert!(output.contains("  jurisdiction: DC\n"));
+        assert!(output.contains("  - BL\n"));
+    }
+
+    #[test]
+    fn test_write_table_is_rejected() {
+        let mut buffer = vec![];
+        let error = write_items(&mut buffer, OutputFormat::Table, &stations(), |info| {
+            StationRecord::from(*info)
+        });
+        assert_eq!(error.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    /// Check trips are written whole as JSON, and flattened for CSV.
+    #[rstest]
+    #[case::from_api("tripinfo_api.json")]
+    #[case::from_docs("tripinfo_docs.json")]
+    fn test_write_trip(#[case] filename: String) {
+        let trip_info: TripInfo = serde_json::from_str(&get_test_data(filename)).unwrap();
+        let trips = [trip_info];
+
+        let mut buffer = vec![];
+        write_items(&mut buffer, OutputFormat::Json, &trips, |trip| {
+            TripRecord::from(trip)
+        })
+        .unwrap();
+        let output: serde_json::Value = serde_json::from_slice(&buffer).unwrap();
+        assert_eq!(
+            output[0]["fromStationTrainStatuses"]
+                .as_array()
+                .unwrap()
+                .len(),
+            trips[0].from_station_train_statuses.len()
+        );
+
+        let mut buffer = vec![];
+        write_items(&mut buffer, OutputFormat::Csv, &trips, |trip| {
+            TripRecord::from(trip)
+        })
+        .unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap().lines().count(), 2);
+    }
+
+    #[test]
+    fn test_write_csv_header_without_items() {
+        let mut buffer = vec![];
+        let no_stations: [&StationInfo; 0] = [];
+        write_items(&mut buffer, OutputFormat::Csv, &no_stations, |info| {
+            StationRecord::from(*info)
+        })
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "code,name,lines,jurisdiction,latitude,longitude,parking\n"
+        );
+    }
+
+    /// Check a record's declared header matches the header serialized along with it.
+    fn assert_header<R: CsvRecord>(record: R) {
+        let mut csv_writer = csv::Writer::from_writer(vec![]);
+        csv_writer.serialize(record).unwrap();
+        let output = String::from_utf8(csv_writer.into_inner().unwrap()).unwrap();
+        assert_eq!(output.lines().next().unwrap(), R::HEADER.join(","));
+    }
+
+    #[test]
+    fn test_csv_headers_match_records() {
+        let trip: TripInfo =
+            serde_json::from_str(&get_test_data(String::from("tripinfo_docs.json"))).unwrap();
+        let train = &trip.from_station_train_statuses[0];
+        assert_header(TripRecord::from(&trip));
+        assert_header(DepartureRecord::from(train));
+        assert_header(TrainRecord::from(train));
+        assert_header(OutageRecord::from(
+            &trip.to_station_escalator_outages.as_ref().unwrap()[0],
+        ));
+        assert_header(AlertRecord::from(&trip.metro_alerts.as_ref().unwrap()[0]));
+        assert_header(TweetRecord::from(&trip.tweets.as_ref().unwrap()[0]));
+
+        let station = StationCode::K04.info().unwrap();
+        assert_header(StationRecord::from(station));
+        assert_header(NearbyStationRecord::from(
+            &StationCode::nearest(station.latitude, station.longitude, 1)[0],
+        ));
+
+        let metrics: SystemMetricsResponse =
+            serde_json::from_str(&get_test_data(String::from("system_metrics_api.json"))).unwrap();
+        assert_header(LineMetricsRecord::from(
+            metrics.line_metrics_by_line.line(LineCode::Red).unwrap(),
+        ));
+
+        let gap: ServiceGaps = serde_json::from_value(serde_json::json!({
+            "lineCode": "RD",
+            "directionNumber": 1,
+            "direction": "Shady Grove",
+            "fromStationCode": "A01",
+            "fromStationName": "Metro Center",
+            "toStationCode": "A02",
+            "toStationName": "Farragut North",
+            "fromTrainId": "101",
+            "toTrainId": "102",
+            "timeBetweenTrains": 12.0,
+            "scheduledTimeBetweenTrains": 6.0,
+            "observedDate": "2022-10-28T20:55:33.618-04:00"
+        }))
+        .unwrap();
+        assert_header(ServiceGapRecord::from(&gap));
+
+        let tags: TrainTags =
+            serde_json::from_str(&get_test_data(String::from("train_report_api.json"))).unwrap();
+        assert_header(TrainDetailRecord::from(&TrainDetails {
+            train_id: &train.train_id,
+            position: Some(train),
+            tags: &tags,
+        }));
+    }
+}
@@ -0,0 +1,266 @@
+#![warn(missing_docs)]
+//! Plan routes between stations offline, using the static [`network`](crate::network) model.
+//!
+//! Routes are planned without any live data, so they are available even when the MetroHero API
+//! is not. They minimize the number of transfers first, and then the number of stops.
+//!
+//! ```
+//! use metrohero_rs::{LineCode, StationCode};
+//! use metrohero_rs::planner::plan_route;
+//!
+//! let route = plan_route(StationCode::K04, StationCode::A15).unwrap();
+//! assert_eq!(route.transfers(), vec![StationCode::C01]); // Metro Center
+//! assert_eq!(route.legs[1].lines, vec![LineCode::Red]);
+//! ```
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::errors::MetroHeroError;
+use crate::network::{self, Direction, LINES};
+use crate::schemas::{LineCode, StationCode};
+
+/// Part of a route ridden on one train, without changing lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Leg {
+    /// The lines whose trains serve every station of this leg, e.g. both Orange and Silver.
+    pub lines: Vec<LineCode>,
+    /// The direction of travel along those lines.
+    pub direction: Direction,
+    /// The stations of this leg in order, from boarding to alighting.
+    pub stations: Vec<StationCode>,
+}
+
+impl Leg {
+    /// The station where the rider boards.
+    pub fn from_station(&self) -> StationCode {
+        self.stations[0]
+    }
+
+    /// The station where the rider alights.
+    pub fn to_station(&self) -> StationCode {
+        self.stations[self.stations.len() - 1]
+    }
+
+    /// The number of stops ridden, not counting the boarding station.
+    pub fn num_stops(&self) -> usize {
+        self.stations.len() - 1
+    }
+}
+
+/// A route between two stations, as a sequence of legs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    /// The legs of the route, in order.
+    pub legs: Vec<Leg>,
+}
+
+impl Route {
+    /// Every station on the route in order, including both platforms of stations transferred at.
+    ///
+    /// For routes without transfers, this matches the API's `TripInfo::trip_station_codes`.
+    pub fn stations(&self) -> Vec<StationCode> {
+        let mut stations: Vec<StationCode> = vec![];
+        for leg in &self.legs {
+            for station in &leg.stations {
+                if stations.last() != Some(station) {
+                    stations.push(*station);
+                }
+            }
+        }
+        stations
+    }
+
+    /// The stations where the rider changes trains.
+    pub fn transfers(&self) -> Vec<StationCode> {
+        self.legs[..self.legs.len() - 1]
+            .iter()
+            .map(Leg::to_station)
+            .collect()
+    }
+
+    /// The number of stops ridden over the whole route.
+    pub fn num_stops(&self) -> usize {
+        self.legs.iter().map(Leg::num_stops).sum()
+    }
+}
+
+/// A position in the search: a station, and the line the rider is on there.
+type State = (StationCode, LineCode);
+
+/// Plan a route from one station to another, with the fewest transfers and then fewest stops.
+///
+/// Returns [`MetroHeroError::InvalidItinerary`] if either station is not on the network, or both
+/// are the same station.
+pub fn plan_route(from: StationCode, to: StationCode) -> Result<Route, MetroHeroError> {
+    let same_station = from == to || network::other_platform(from) == Some(to);
+    if same_station || network::lines_serving(to).is_empty() {
+        return Err(MetroHeroError::InvalidItinerary { request_path: None });
+    }
+
+    // Dijkstra's algorithm over (station, line) states, costed by (transfers, stops).
+    let mut costs: HashMap<State, (usize, usize)> = HashMap::new();
+    let mut previous: HashMap<State, State> = HashMap::new();
+    // Queued states are referred to by their index in `queued`, which breaks ties between equally
+    // costly states in the order they were found.
+    let mut queued: Vec<State> = vec![];
+    let mut queue = BinaryHeap::new();
+    for line in network::lines_serving(from) {
+        costs.insert((from, line), (0, 0));
+        queue.push(Reverse(((0, 0), queued.len())));
+        queued.push((from, line));
+    }
+
+    let mut destination = None;
+    while let Some(Reverse(((transfers, stops), index))) = queue.pop() {
+        let (station, line) = queued[index];
+        if costs.get(&(station, line)) != Some(&(transfers, stops)) {
+            continue;
+        }
+        if station == to {
+            destination = Some((station, line));
+            break;
+        }
+
+        let rides = [Direction::D1, Direction::D2]
+            .into_iter()
+            .filter_map(|direction| network::next_station(station, line, direction))
+            .map(|next| ((next, line), (transfers, stops + 1)));
+        let platforms = std::iter::once(station).chain(network::other_platform(station));
+        let changes = platforms
+            .flat_map(|platform| {
+                network::lines_serving(platform)
+                    .into_iter()
+                    .map(move |other| (platform, other))
+            })
+            .filter(|next| *next != (station, line))
+            .map(|next| (next, (transfers + 1, stops)));
+
+        for (next, cost) in rides.chain(changes) {
+            if costs.get(&next).is_none_or(|best| cost < *best) {
+                costs.insert(next, cost);
+                previous.insert(next, (station, line));
+                queue.push(Reverse((cost, queued.len())));
+                queued.push(next);
+            }
+        }
+    }
+
+    let mut path =
+        vec![destination.ok_or(MetroHeroError::InvalidItinerary { request_path: None })?];
+    while let Some(state) = previous.get(&path[path.len() - 1]) {
+        path.push(*state);
+    }
+    path.reverse();
+    Ok(Route {
+        legs: split_legs(&path),
+    })
+}
+
+/// Break a path of states into legs, one per line ridden.
+fn split_legs(path: &[State]) -> Vec<Leg> {
+    let mut legs = vec![];
+    let mut start = 0;
+    for end in 1..=path.len() {
+        if end < path.len() && path[end].1 == path[start].1 {
+            continue;
+        }
+        let stations: Vec<StationCode> = path[start..end].iter().map(|(s, _)| *s).collect();
+        if stations.len() > 1 {
+            legs.push(leg_for(path[start].1, stations));
+        }
+        start = end;
+    }
+    legs
+}
+
+/// Describe a leg ridden on `line`, along with any other lines serving the same stations.
+fn leg_for(line: LineCode, stations: Vec<StationCode>) -> Leg {
+    let direction = match network::next_station(stations[0], line, Direction::D1) {
+        Some(next) if next == stations[1] => Direction::D1,
+        _ => Direction::D2,
+    };
+    let lines = LINES
+        .into_iter()
+        .filter(|other| {
+            stations
+                .windows(2)
+                .all(|pair| network::next_station(pair[0], *other, direction) == Some(pair[1]))
+        })
+        .collect();
+    Leg {
+        lines,
+        direction,
+        stations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::MetroHeroError;
+    use crate::network::Direction;
+    use crate::planner::plan_route;
+    use crate::schemas::tests::get_test_data;
+    use crate::schemas::{LineCode, StationCode, TripInfo};
+    use rstest::rstest;
+
+    #[test]
+    fn test_single_leg_route() {
+        let route = plan_route(StationCode::K03, StationCode::C02).unwrap();
+        assert_eq!(route.legs.len(), 1);
+        assert_eq!(
+            route.legs[0].lines,
+            vec![LineCode::Orange, LineCode::Silver]
+        );
+        assert_eq!(route.legs[0].direction, Direction::D1);
+        assert_eq!(route.num_stops(), 6);
+        assert!(route.transfers().is_empty());
+    }
+
+    #[test]
+    fn test_route_with_transfer() {
+        let route = plan_route(StationCode::K04, StationCode::A15).unwrap();
+        assert_eq!(route.transfers(), vec![StationCode::C01]);
+        assert_eq!(
+            route.legs[0].lines,
+            vec![LineCode::Orange, LineCode::Silver]
+        );
+        assert_eq!(route.legs[1].lines, vec![LineCode::Red]);
+        assert_eq!(route.legs[1].direction, Direction::D2);
+        assert_eq!(route.legs[1].from_station(), StationCode::A01);
+        assert_eq!(route.num_stops(), 8 + 14);
+    }
+
+    #[test]
+    fn test_route_prefers_fewer_transfers() {
+        // Court House to Pentagon City changes to the Blue line at Rosslyn, where it begins.
+        let route = plan_route(StationCode::K01, StationCode::C08).unwrap();
+        assert_eq!(route.transfers(), vec![StationCode::C05]);
+        let route = plan_route(StationCode::C05, StationCode::C08).unwrap();
+        assert_eq!(route.legs.len(), 1);
+        assert_eq!(route.legs[0].lines, vec![LineCode::Blue]);
+    }
+
+    #[rstest]
+    #[case::same_station(StationCode::K04, StationCode::K04)]
+    #[case::same_complex(StationCode::A01, StationCode::C01)]
+    #[case::unknown(StationCode::K04, StationCode::UNKNOWN)]
+    fn test_invalid_itinerary(#[case] from: StationCode, #[case] to: StationCode) {
+        assert_eq!(
+            plan_route(from, to).unwrap_err(),
+            MetroHeroError::InvalidItinerary { request_path: None }
+        );
+    }
+
+    /// Cross-check planned routes against the routes reported by the API.
+    #[rstest]
+    #[case::from_api("tripinfo_api.json")]
+    #[case::from_api2("tripinfo_api2.json")]
+    #[case::from_api_na("tripinfo_api_na_trains.json")]
+    #[case::from_docs("tripinfo_docs.json")]
+    fn test_matches_trip_info(#[case] filename: String) {
+        let trip_info: TripInfo = serde_json::from_str(&get_test_data(filename)).unwrap();
+        let route = plan_route(trip_info.from_station_code, trip_info.to_station_code).unwrap();
+        assert_eq!(route.stations(), trip_info.trip_station_codes);
+        assert_eq!(route.legs[0].lines, trip_info.line_codes);
+    }
+}
@@ -0,0 +1,194 @@
+#![warn(missing_docs)]
+//! Client-side rate limiting, to stay within the MetroHero API's request quotas.
+//!
+//! The MetroHero API allows 10 requests per second and 50,000 requests per rolling 24 hours for
+//! each API key. Exceeding either limit results in a [`MetroHeroError::RateLimited`] response.
+//! A [`RateLimiter`] delays requests to stay under the per-second limit, and refuses them with
+//! [`MetroHeroError::QuotaExhausted`] once its 24-hour budget is spent.
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::errors::MetroHeroError;
+
+/// Requests permitted per second by the MetroHero API.
+pub const REQUESTS_PER_SECOND: usize = 10;
+
+/// Requests permitted per rolling 24 hours by the MetroHero API.
+pub const REQUESTS_PER_DAY: usize = 50_000;
+
+const SECOND: Duration = Duration::from_secs(1);
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Requests which may still be made without exceeding a [`RateLimiter`]'s limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    /// Requests which may be sent immediately without exceeding the per-second limit.
+    pub remaining_this_second: usize,
+    /// Requests which may be sent before the rolling 24-hour limit is reached.
+    pub remaining_today: usize,
+}
+
+/// Throttles requests to stay within per-second and rolling 24-hour limits.
+///
+/// A single limiter is safe to share between threads, and between several clients which use the
+/// same API key.
+///
+/// # Example
+///
+/// ```
+/// use metrohero_rs::MetroHeroClient;
+/// use metrohero_rs::ratelimit::RateLimiter;
+///
+/// let client = MetroHeroClient::builder("YOUR_API_KEY".to_string())
+///     .rate_limiter(RateLimiter::default())
+///     .build()
+///     .unwrap();
+/// assert_eq!(client.remaining_quota().unwrap().remaining_today, 50_000);
+/// ```
+#[derive(Debug)]
+pub struct RateLimiter {
+    per_second: usize,
+    per_day: usize,
+    /// Times at which requests within the last 24 hours were, or will be, permitted to start.
+    permits: Mutex<VecDeque<Instant>>,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter allowing `per_second` requests per second and `per_day` requests per
+    /// rolling 24 hours.
+    pub fn new(per_second: usize, per_day: usize) -> Self {
+        Self {
+            per_second: per_second.max(1),
+            per_day,
+            permits: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    /// Reserve a slot for a request, returning how long the caller must wait before sending it.
+    ///
+    /// Returns [`MetroHeroError::QuotaExhausted`] if the rolling 24-hour budget has been spent.
+    pub fn reserve(&self) -> Result<Duration, MetroHeroError> {
+        let now = Instant::now();
+        let mut permits = self.permits.lock().unwrap();
+        Self::expire(&mut permits, now);
+
+        if permits.len() >= self.per_day {
+            let retry_after = permits
+                .front()
+                .map_or(DAY, |oldest| (*oldest + DAY).saturating_duration_since(now));
+            return Err(MetroHeroError::QuotaExhausted { retry_after });
+        }
+
+        // Permits are granted in order, so the request must wait until the permit `per_second`
+        // places before it has left the one second window.
+        let mut permitted_at = now;
+        if permits.len() >= self.per_second {
+            let blocking_permit = permits[permits.len() - self.per_second];
+            permitted_at = permitted_at.max(blocking_permit + SECOND);
+        }
+        permits.push_back(permitted_at);
+
+        Ok(permitted_at - now)
+    }
+
+    /// Get the requests which may still be made without exceeding this limiter's limits.
+    pub fn remaining(&self) -> Quota {
+        let now = Instant::now();
+        let mut permits = self.permits.lock().unwrap();
+        Self::expire(&mut permits, now);
+
+        let used_this_second = permits
+            .iter()
+            .rev()
+            .take_while(|permit| now < **permit + SECOND)
+            .count();
+
+        Quota {
+            remaining_this_second: self.per_second.saturating_sub(used_this_second),
+            remaining_today: self.per_day.saturating_sub(permits.len()),
+        }
+    }
+
+    /// Forget permits which have left the rolling 24-hour window.
+    fn expire(permits: &mut VecDeque<Instant>, now: Instant) {
+        while let Some(permit) = permits.front() {
+            if now.saturating_duration_since(*permit) >= DAY {
+                permits.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    /// A rate limiter matching the MetroHero API's limits of 10 requests per second and 50,000
+    /// requests per 24 hours.
+    fn default() -> Self {
+        RateLimiter::new(REQUESTS_PER_SECOND, REQUESTS_PER_DAY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::MetroHeroError;
+    use crate::ratelimit::{Quota, RateLimiter, DAY};
+    use std::time::Duration;
+
+    #[test]
+    fn test_per_second_burst_is_throttled() {
+        let limiter = RateLimiter::new(2, 100);
+        assert_eq!(limiter.reserve().unwrap(), Duration::ZERO);
+        assert_eq!(limiter.reserve().unwrap(), Duration::ZERO);
+
+        let wait = limiter.reserve().unwrap();
+        assert!(wait > Duration::from_millis(900), "waited {wait:?}");
+        assert!(wait <= Duration::from_secs(1), "waited {wait:?}");
+
+        // The next request queues behind the one above.
+        let wait = limiter.reserve().unwrap();
+        assert!(wait > Duration::from_millis(900), "waited {wait:?}");
+    }
+
+    #[test]
+    fn test_daily_budget_is_enforced() {
+        let limiter = RateLimiter::new(10, 3);
+        for _ in 0..3 {
+            limiter.reserve().unwrap();
+        }
+        match limiter.reserve().unwrap_err() {
+            MetroHeroError::QuotaExhausted { retry_after } => {
+                assert!(
+                    retry_after > DAY - Duration::from_secs(60),
+                    "{retry_after:?}"
+                );
+                assert!(retry_after <= DAY, "{retry_after:?}");
+            }
+            error => panic!("Expected QuotaExhausted, got {error:?}"),
+        }
+    }
+
+    #[test]
+    fn test_remaining() {
+        let limiter = RateLimiter::new(10, 50);
+        assert_eq!(
+            limiter.remaining(),
+            Quota {
+                remaining_this_second: 10,
+                remaining_today: 50
+            }
+        );
+
+        for _ in 0..4 {
+            limiter.reserve().unwrap();
+        }
+        assert_eq!(
+            limiter.remaining(),
+            Quota {
+                remaining_this_second: 6,
+                remaining_today: 46
+            }
+        );
+    }
+}
@@ -0,0 +1,230 @@
+#![warn(missing_docs)]
+//! Retrying requests which fail for transient reasons, with exponential backoff.
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rand::Rng;
+
+use crate::errors::MetroHeroError;
+
+/// Details of a failed request which is about to be retried.
+#[derive(Debug)]
+pub struct RetryEvent<'a> {
+    /// The path of the request being retried.
+    pub request_path: &'a str,
+    /// The attempt which failed, starting from 1.
+    pub attempt: u32,
+    /// How long the client will wait before the next attempt.
+    pub delay: Duration,
+    /// The error which caused the attempt to fail.
+    pub error: &'a MetroHeroError,
+}
+
+type RetryPredicate = dyn Fn(&MetroHeroError) -> bool + Send + Sync;
+type RetryCallback = dyn Fn(&RetryEvent) + Send + Sync;
+
+/// Controls how, and how often, failed requests are retried.
+///
+/// Delays double after each attempt, starting at the base delay and capped at the maximum delay.
+/// With jitter enabled, each delay is randomized between half and all of its full value so that
+/// clients sharing an API key do not retry in lockstep.
+///
+/// # Example
+///
+/// ```
+/// use std::time::Duration;
+/// use metrohero_rs::MetroHeroClient;
+/// use metrohero_rs::retry::RetryPolicy;
+///
+/// let policy = RetryPolicy::new(5)
+///     .base_delay(Duration::from_millis(250))
+///     .on_retry(|event| eprintln!("Retrying {}: {}", event.request_path, event.error));
+///
+/// let client = MetroHeroClient::builder("YOUR_API_KEY".to_string())
+///     .retry_policy(policy)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    retryable: Arc<RetryPredicate>,
+    on_retry: Option<Arc<RetryCallback>>,
+}
+
+impl RetryPolicy {
+    /// Create a policy which makes at most `max_attempts` attempts at each request.
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            jitter: true,
+            retryable: Arc::new(is_transient),
+            on_retry: None,
+        }
+    }
+
+    /// Set the delay before the first retry. Defaults to 500ms.
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Set the longest delay between attempts. Defaults to 10s.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Randomize delays between attempts. Defaults to `true`.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Set which errors are retried.
+    ///
+    /// By default, rate limiting, connection failures and 5xx responses are retried.
+    pub fn retry_if(
+        mut self,
+        retryable: impl Fn(&MetroHeroError) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.retryable = Arc::new(retryable);
+        self
+    }
+
+    /// Call `on_retry` each time a failed request is about to be retried.
+    pub fn on_retry(mut self, on_retry: impl Fn(&RetryEvent) + Send + Sync + 'static) -> Self {
+        self.on_retry = Some(Arc::new(on_retry));
+        self
+    }
+
+    /// The full delay before the attempt after `attempt`, before any jitter is applied.
+    pub fn delay_for(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        self.base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay)
+    }
+
+    /// Decide whether a failed attempt should be retried, returning the delay before retrying.
+    pub(crate) fn next_delay(
+        &self,
+        request_path: &str,
+        attempt: u32,
+        error: &MetroHeroError,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts || !(self.retryable)(error) {
+            return None;
+        }
+
+        let mut delay = self.delay_for(attempt);
+        if self.jitter && !delay.is_zero() {
+            delay = rand::thread_rng().gen_range(delay / 2..=delay);
+        }
+
+        if let Some(on_retry) = &self.on_retry {
+            on_retry(&RetryEvent {
+                request_path,
+                attempt,
+                delay,
+                error,
+            });
+        }
+        Some(delay)
+    }
+}
+
+impl Default for RetryPolicy {
+    /// A policy making up to 3 attempts at each request.
+    fn default() -> Self {
+        RetryPolicy::new(3)
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("base_delay", &self.base_delay)
+            .field("max_delay", &self.max_delay)
+            .field("jitter", &self.jitter)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Whether an error is likely to be resolved by retrying the request.
+///
+/// Requests refused by the client's own [`RateLimiter`](crate::ratelimit::RateLimiter) with
+/// [`MetroHeroError::QuotaExhausted`] are not retried, as the budget takes hours to recover.
+pub fn is_transient(error: &MetroHeroError) -> bool {
+    match error {
+        MetroHeroError::RateLimited { .. } | MetroHeroError::HttpError { .. } => true,
+        MetroHeroError::UnexpectedStatus { status, .. } => *status >= 500,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::MetroHeroError;
+    use crate::retry::RetryPolicy;
+    use std::time::Duration;
+
+    #[test]
+    fn test_delay_for_is_exponential_and_capped() {
+        let policy = RetryPolicy::new(10)
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(500));
+        assert_eq!(policy.delay_for(1), Duration::from_millis(100));
+        assert_eq!(policy.delay_for(2), Duration::from_millis(200));
+        assert_eq!(policy.delay_for(3), Duration::from_millis(400));
+        assert_eq!(policy.delay_for(4), Duration::from_millis(500));
+        assert_eq!(policy.delay_for(100), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_next_delay_with_jitter() {
+        let policy = RetryPolicy::new(3).base_delay(Duration::from_millis(100));
+        let delay = policy
+            .next_delay("/", 1, &MetroHeroError::RateLimited { request_path: None })
+            .unwrap();
+        assert!(delay >= Duration::from_millis(50) && delay <= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_next_delay_gives_up() {
+        let policy = RetryPolicy::new(2).jitter(false);
+        assert_eq!(
+            policy.next_delay("/", 1, &MetroHeroError::RateLimited { request_path: None }),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            policy.next_delay("/", 2, &MetroHeroError::RateLimited { request_path: None }),
+            None
+        );
+        assert_eq!(
+            policy.next_delay(
+                "/",
+                1,
+                &MetroHeroError::InvalidStation { request_path: None }
+            ),
+            None
+        );
+        assert_eq!(
+            policy.next_delay(
+                "/",
+                1,
+                &MetroHeroError::QuotaExhausted {
+                    retry_after: Duration::from_secs(60)
+                }
+            ),
+            None
+        );
+    }
+}
@@ -1,10 +1,21 @@
 //! Schemas describing data returned by the MetroHero API.
+//!
+//! Timestamps are kept as the strings returned by the API rather than deserialized into datetime
+//! types, so a timestamp in an unexpected format does not fail the whole response. Instead, the
+//! `chrono` feature provides accessors, such as `Tweet::date_time`, which parse them into
+//! `chrono::DateTime`s in the `America/New_York` timezone.
 use crate::errors::MetroHeroError;
+use crate::network::{Direction, LINES};
+use crate::search::{search_stations, StationMatch};
+use crate::stations::{nearest_stations, station_info, NearbyStation, StationInfo};
 use crossterm::style::Color;
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Formatter;
+use std::str::FromStr;
+use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter, EnumString};
 
 /// Train reports are a map between AIMS ID (as strings) and their report tags.
@@ -133,7 +144,7 @@ lazy_static! {
 }
 
 lazy_static! {
-    static ref STATION_NAME_TO_CODE: HashMap<&'static str, StationCode> = {
+    pub(crate) static ref STATION_NAME_TO_CODE: HashMap<&'static str, StationCode> = {
         let mut m = HashMap::new();
         // TODO: Figure out how to deal with stations with more than one code.
         m.insert("Metro Center", StationCode::A01);
@@ -395,10 +406,30 @@ impl StationCode {
         let result = STATION_NAME_TO_CODE.get(name);
         match result {
             Some(code) => Ok(*code),
-            None => Err(MetroHeroError::InvalidStation),
+            None => Err(MetroHeroError::InvalidStation { request_path: None }),
         }
     }
 
+    /// Search for stations by code or name, tolerating differences in case and punctuation,
+    /// abbreviations and typos. Matches are ranked best first.
+    ///
+    /// Use [`from_name`](StationCode::from_name) to look up a station by its exact name.
+    pub fn search(query: &str) -> Vec<StationMatch> {
+        search_stations(query)
+    }
+
+    /// Get metadata about a station, such as its location and the lines serving it.
+    ///
+    /// Returns `None` for [`StationCode::UNKNOWN`].
+    pub fn info(&self) -> Option<&'static StationInfo> {
+        station_info(*self)
+    }
+
+    /// Find the `n` stations closest to a location, nearest first, with their distances.
+    pub fn nearest(latitude: f64, longitude: f64, n: usize) -> Vec<NearbyStation> {
+        nearest_stations(latitude, longitude, n)
+    }
+
     /// Returns the friendly name of a station.
     pub fn to_name(&self) -> &'static str {
         let name = STATION_CODE_TO_NAME.get(self).unwrap();
@@ -414,6 +445,14 @@ pub struct SystemMetricsResponse {
     pub date: String,
 }
 
+impl SystemMetricsResponse {
+    /// When the metrics were calculated, if [`date`](Self::date) is in a known format.
+    #[cfg(feature = "chrono")]
+    pub fn date_time(&self) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        crate::datetime::parse_timestamp(&self.date)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 /// Line-specific metrics by line code.
@@ -431,6 +470,28 @@ pub struct SystemMetrics {
     #[serde(rename = "GR")]
     pub gr: LineMetrics,
 }
+impl SystemMetrics {
+    /// Get the metrics for a line, or `None` for [`LineCode::NonRevenue`].
+    pub fn line(&self, line: LineCode) -> Option<&LineMetrics> {
+        match line {
+            LineCode::Red => Some(&self.rd),
+            LineCode::Orange => Some(&self.or),
+            LineCode::Silver => Some(&self.sv),
+            LineCode::Blue => Some(&self.bl),
+            LineCode::Yellow => Some(&self.yl),
+            LineCode::Green => Some(&self.gr),
+            LineCode::NonRevenue => None,
+        }
+    }
+
+    /// Iterate over the metrics for every line.
+    pub fn iter(&self) -> impl Iterator<Item = (LineCode, &LineMetrics)> {
+        LINES
+            .into_iter()
+            .filter_map(|line| self.line(line).map(|metrics| (line, metrics)))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 /// Line metrics for a specific line.
@@ -461,6 +522,14 @@ pub struct LineMetrics {
     pub expected_standard_deviation_train_frequency: Option<f64>,
 }
 
+impl LineMetrics {
+    /// When the metrics were calculated, if [`date`](Self::date) is in a known format.
+    #[cfg(feature = "chrono")]
+    pub fn date_time(&self) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        crate::datetime::parse_timestamp(&self.date)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 /// Train metrics for a specific Metrorail line.
@@ -471,6 +540,28 @@ pub struct DirectionMetricsByDirection {
     pub d2: DirectionMetrics,
 }
 
+impl DirectionMetricsByDirection {
+    /// Get the metrics for trains travelling in a direction.
+    pub fn get(&self, direction: Direction) -> &DirectionMetrics {
+        match direction {
+            Direction::D1 => &self.d1,
+            Direction::D2 => &self.d2,
+        }
+    }
+
+    /// Get the metrics for a direction number reported by the API, which is either 1 or 2.
+    pub fn by_number(&self, direction_number: i64) -> Option<&DirectionMetrics> {
+        Direction::from_number(direction_number).map(|direction| self.get(direction))
+    }
+
+    /// Iterate over the metrics for both directions.
+    pub fn iter(&self) -> impl Iterator<Item = (Direction, &DirectionMetrics)> {
+        [Direction::D1, Direction::D2]
+            .into_iter()
+            .map(|direction| (direction, self.get(direction)))
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 /// Direction metrics for a specific line and direction.
@@ -504,6 +595,14 @@ pub struct DirectionMetrics {
     pub expected_standard_deviation_train_frequency: Option<f64>,
 }
 
+impl DirectionMetrics {
+    /// When the metrics were calculated, if [`date`](Self::date) is in a known format.
+    #[cfg(feature = "chrono")]
+    pub fn date_time(&self) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        crate::datetime::parse_timestamp(&self.date)
+    }
+}
+
 /// Real-time and scheduled train predictions for a particular station.
 ///
 /// This API returns all of the same data as WMATA's Real-Time Rail Predictions API, but with
@@ -538,6 +637,14 @@ pub struct TripInfo {
     pub to_station_escalator_outages: Option<Vec<ElevatorEscalatorOutage>>,
 }
 
+impl TripInfo {
+    /// When the trip information was calculated, if [`date`](Self::date) is in a known format.
+    #[cfg(feature = "chrono")]
+    pub fn date_time(&self) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        crate::datetime::parse_timestamp(&self.date)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 /// An alert issued by WMATA.
@@ -549,6 +656,14 @@ pub struct MetroAlert {
     pub date: String,
 }
 
+impl MetroAlert {
+    /// When the alert was issued, if [`date`](Self::date) is in a known format.
+    #[cfg(feature = "chrono")]
+    pub fn date_time(&self) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        crate::datetime::parse_timestamp(&self.date)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 /// Tweet referencing a Metrorail station, line, or train.
@@ -564,6 +679,14 @@ pub struct Tweet {
     pub date: String,
 }
 
+impl Tweet {
+    /// When the tweet was posted, if [`date`](Self::date) is in a known format.
+    #[cfg(feature = "chrono")]
+    pub fn date_time(&self) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        crate::datetime::parse_timestamp(&self.date)
+    }
+}
+
 /// A truncated tweet returned as a child on a TrainPrediction.
 /// Not documented.
 #[derive(Debug, Serialize, Deserialize)]
@@ -600,6 +723,26 @@ pub struct ElevatorEscalatorOutage {
     pub estimated_return_to_service_date: String,
 }
 
+impl ElevatorEscalatorOutage {
+    /// When the unit went out of service, if [`out_of_service_date`](Self::out_of_service_date) is in a known format.
+    #[cfg(feature = "chrono")]
+    pub fn out_of_service_date_time(&self) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        crate::datetime::parse_timestamp(&self.out_of_service_date)
+    }
+
+    /// When the outage was last updated, if [`updated_date`](Self::updated_date) is in a known format.
+    #[cfg(feature = "chrono")]
+    pub fn updated_date_time(&self) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        crate::datetime::parse_timestamp(&self.updated_date)
+    }
+
+    /// When the unit is expected to return to service, if [`estimated_return_to_service_date`](Self::estimated_return_to_service_date) is in a known format.
+    #[cfg(feature = "chrono")]
+    pub fn estimated_return_to_service_date_time(&self) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        crate::datetime::parse_timestamp(&self.estimated_return_to_service_date)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 /// Predicted arrival information about Metrorail train.
@@ -654,26 +797,151 @@ pub struct TrainPrediction {
     pub recent_tweets: Option<RecentTweets>,
 }
 impl TrainPrediction {
+    /// When the train was observed, if [`observed_date`](Self::observed_date) is in a known format.
+    #[cfg(feature = "chrono")]
+    pub fn observed_date_time(&self) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        crate::datetime::parse_timestamp(&self.observed_date)
+    }
+
+    /// How long ago the train was observed, if the time it was observed could be parsed.
+    #[cfg(feature = "chrono")]
+    pub fn observation_age(&self) -> Option<chrono::Duration> {
+        self.observed_date_time()
+            .map(|observed| crate::datetime::age(&observed))
+    }
+
+    /// Parse the train's ETA from its `min` value.
+    pub fn eta(&self) -> Eta {
+        Eta::from(self.min.as_str())
+    }
+
     /// For a given train ETA value, render a more legible version.
     pub fn eta_minutes(&self) -> String {
-        let mut eta_time = self.min.clone();
-
-        let mut needs_min_invalidator = true;
-        let non_time_min_values = vec!["ARR", "BRD", "?", ":"];
-        for value in non_time_min_values {
-            if self.min.as_str().contains(value) {
-                needs_min_invalidator = false
-            };
+        self.eta().to_string()
+    }
+
+    /// Compare trains by how soon they are expected at the station, e.g. to sort departures.
+    ///
+    /// Trains are compared by their `minutes_away` when both have one, so live predictions and
+    /// scheduled departures are interleaved, and scheduled departures on either side of 12
+    /// o'clock are ordered correctly. Otherwise, or on a tie, they are compared by [`Eta`].
+    pub fn cmp_departure(&self, other: &Self) -> Ordering {
+        let by_eta = || self.eta().cmp(&other.eta());
+        match (self.minutes_away, other.minutes_away) {
+            (Some(a), Some(b)) => a.total_cmp(&b).then_with(by_eta),
+            _ => by_eta(),
         }
+    }
+}
 
-        if needs_min_invalidator {
-            eta_time.push('m');
+/// When a train is expected at a station, parsed from [`TrainPrediction::min`].
+///
+/// ETAs are ordered by how soon the train is expected: boarding trains first, then arriving
+/// trains, live predictions in minutes, scheduled departures, and finally delayed trains and
+/// trains without a prediction. Scheduled departures are ordered on a 12-hour clock starting at
+/// 12, so `12:50` comes before `1:05`; use [`TrainPrediction::cmp_departure`] to also order
+/// departures on either side of 12 o'clock, such as `11:50` and `12:05`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Eta {
+    /// The train is boarding at the station (`BRD`).
+    Boarding,
+    /// The train is arriving at the station (`ARR`).
+    Arriving,
+    /// The train is predicted to arrive in this many minutes.
+    Minutes(u32),
+    /// The train is scheduled, rather than tracked live, to depart at this clock time, e.g. `12:20`.
+    Scheduled {
+        /// The hour of the scheduled departure, on a 12-hour clock.
+        hour: u8,
+        /// The minute of the scheduled departure.
+        minute: u8,
+    },
+    /// The train is delayed (`DLY`).
+    Delayed,
+    /// No prediction is available for the train, e.g. `?` or `---`.
+    Unknown,
+}
+
+impl Eta {
+    /// Whether the train is boarding or arriving at the station.
+    pub fn is_imminent(&self) -> bool {
+        matches!(self, Eta::Boarding | Eta::Arriving)
+    }
+
+    /// The key ETAs are ordered by: their kind, then how many minutes away they are, with
+    /// scheduled departures counted in minutes past 12 o'clock.
+    fn sort_key(&self) -> (u8, u32) {
+        match *self {
+            Eta::Boarding => (0, 0),
+            Eta::Arriving => (1, 0),
+            Eta::Minutes(minutes) => (2, minutes),
+            Eta::Scheduled { hour, minute } => (3, u32::from(hour % 12) * 60 + u32::from(minute)),
+            Eta::Delayed => (4, 0),
+            Eta::Unknown => (5, 0),
         }
-        eta_time
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl Ord for Eta {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.sort_key()
+            .cmp(&other.sort_key())
+            .then_with(|| match (self, other) {
+                // Keep the ordering consistent with equality for out-of-range clock times.
+                (
+                    Eta::Scheduled { hour, minute },
+                    Eta::Scheduled {
+                        hour: other_hour,
+                        minute: other_minute,
+                    },
+                ) => (hour, minute).cmp(&(other_hour, other_minute)),
+                _ => Ordering::Equal,
+            })
+    }
+}
+
+impl PartialOrd for Eta {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl From<&str> for Eta {
+    fn from(min: &str) -> Self {
+        let min = min.trim();
+        match min {
+            "BRD" => return Eta::Boarding,
+            "ARR" => return Eta::Arriving,
+            "DLY" => return Eta::Delayed,
+            _ => {}
+        }
+
+        if let Ok(minutes) = min.parse() {
+            return Eta::Minutes(minutes);
+        }
+        if let Some((hour, minute)) = min.split_once(':') {
+            if let (Ok(hour), Ok(minute)) = (hour.parse(), minute.parse()) {
+                return Eta::Scheduled { hour, minute };
+            }
+        }
+        Eta::Unknown
+    }
+}
+
+impl fmt::Display for Eta {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Eta::Boarding => write!(f, "BRD"),
+            Eta::Arriving => write!(f, "ARR"),
+            Eta::Minutes(minutes) => write!(f, "{minutes}m"),
+            Eta::Scheduled { hour, minute } => write!(f, "{hour}:{minute:02}"),
+            Eta::Delayed => write!(f, "DLY"),
+            Eta::Unknown => write!(f, "?"),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, EnumIter)]
 /// Metrorail line codes.
 pub enum LineCode {
     #[serde(rename = "SV")]
@@ -700,12 +968,54 @@ impl LineCode {
             LineCode::Silver => Color::Grey,
             LineCode::Green => Color::Green,
             LineCode::Yellow => Color::Yellow,
-            LineCode::Blue => Color::Red,
+            LineCode::Blue => Color::Blue,
             LineCode::Orange => Color::DarkYellow,
             LineCode::Red => Color::Red,
             LineCode::NonRevenue => Color::DarkMagenta,
         }
     }
+
+    /// Get the official color of a line, as a hex RGB string such as `#BF0D3E`.
+    ///
+    /// Non-revenue trains have no official color.
+    pub fn hex_color(&self) -> Option<&'static str> {
+        match self {
+            LineCode::Silver => Some("#919D9D"),
+            LineCode::Red => Some("#BF0D3E"),
+            LineCode::Orange => Some("#ED8B00"),
+            LineCode::Blue => Some("#009CDE"),
+            LineCode::Yellow => Some("#FFD100"),
+            LineCode::Green => Some("#00B140"),
+            LineCode::NonRevenue => None,
+        }
+    }
+
+    /// Get the full name of a line, e.g. `Red` for [`LineCode::Red`].
+    pub fn name(&self) -> &'static str {
+        match self {
+            LineCode::Silver => "Silver",
+            LineCode::Red => "Red",
+            LineCode::Orange => "Orange",
+            LineCode::Blue => "Blue",
+            LineCode::Yellow => "Yellow",
+            LineCode::Green => "Green",
+            LineCode::NonRevenue => "Non-revenue",
+        }
+    }
+}
+
+impl FromStr for LineCode {
+    type Err = strum::ParseError;
+
+    /// Parse a line from its code, e.g. `RD`, or its name, e.g. `Red`, ignoring case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        LineCode::iter()
+            .find(|line| {
+                line.to_string().eq_ignore_ascii_case(s) || line.name().eq_ignore_ascii_case(s)
+            })
+            .ok_or(strum::ParseError::VariantNotFound)
+    }
 }
 
 impl fmt::Display for LineCode {
@@ -807,6 +1117,26 @@ pub struct ServiceGaps {
     pub observed_date: String,
 }
 
+impl ServiceGaps {
+    /// How many minutes longer than scheduled riders are waiting between trains.
+    pub fn severity(&self) -> f64 {
+        self.time_between_trains - self.scheduled_time_between_trains
+    }
+
+    /// When the gap was observed, if [`observed_date`](Self::observed_date) is in a known format.
+    #[cfg(feature = "chrono")]
+    pub fn observed_date_time(&self) -> Option<chrono::DateTime<chrono_tz::Tz>> {
+        crate::datetime::parse_timestamp(&self.observed_date)
+    }
+
+    /// How long ago the gap was observed, if the time it was observed could be parsed.
+    #[cfg(feature = "chrono")]
+    pub fn observation_age(&self) -> Option<chrono::Duration> {
+        self.observed_date_time()
+            .map(|observed| crate::datetime::age(&observed))
+    }
+}
+
 #[derive(Default, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 /// Counts of user-reported issues with a Metrorail train.
@@ -843,6 +1173,35 @@ pub struct NumTrainTagsByType {
     pub wrong_num_cars: i64,
 }
 
+impl NumTrainTagsByType {
+    /// Counts of reports about problems with the train, in alphabetical order of tag.
+    pub fn bad_tags(&self) -> Vec<(&'static str, i64)> {
+        vec![
+            ("BAD_OPERATOR", self.bad_operator),
+            ("BROKEN_INTERCOM", self.broken_intercom),
+            ("CROWDED", self.crowded),
+            ("DISRUPTIVE_PASSENGER", self.disruptive_passenger),
+            ("ISOLATED_CARS", self.isolated_cars),
+            ("NEEDS_WORK", self.needs_work),
+            ("RECENTLY_OFFLOADED", self.recently_offloaded),
+            ("UNCOMFORTABLE_RIDE", self.uncomfortable_ride),
+            ("UNCOMFORTABLE_TEMPS", self.uncomfortable_temps),
+            ("WRONG_DESTINATION", self.wrong_destination),
+            ("WRONG_NUM_CARS", self.wrong_num_cars),
+        ]
+    }
+
+    /// Counts of reports praising the train, in alphabetical order of tag.
+    pub fn good_tags(&self) -> Vec<(&'static str, i64)> {
+        vec![
+            ("EMPTY", self.empty),
+            ("GOOD_OPERATOR", self.good_operator),
+            ("GOOD_RIDE", self.good_ride),
+            ("NEW_TRAIN", self.new_train),
+        ]
+    }
+}
+
 #[derive(Default, Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 /// User-reported information about a Metrorail train.
@@ -855,12 +1214,15 @@ pub struct TrainTags {
 #[cfg(test)]
 pub mod tests {
     use crate::schemas::{
-        StationReports, StationTags, TrainPrediction, TrainPredictions, TrainReports, TrainTags,
-        TripInfo, Tweet,
+        Eta, LineCode, StationReports, StationTags, SystemMetricsResponse, TrainPrediction,
+        TrainPredictions, TrainReports, TrainTags, TripInfo, Tweet,
     };
+    use crossterm::style::Color;
     use rstest::rstest;
     use std::path::PathBuf;
+    use std::str::FromStr;
     use std::{env, fs};
+    use strum::IntoEnumIterator;
 
     /// Get test data by filename.
     pub fn get_test_data(filename: String) -> String {
@@ -942,6 +1304,15 @@ pub mod tests {
         let _train_reports: TrainTags = serde_json::from_str(data.as_str()).unwrap();
     }
 
+    /// Check every train tag is counted as either good or bad.
+    #[test]
+    fn test_train_tags_by_kind() {
+        let tags = TrainTags::default().num_tags_by_type;
+        assert_eq!(tags.bad_tags().len() + tags.good_tags().len(), 15);
+        assert!(tags.good_tags().contains(&("GOOD_RIDE", 0)));
+        assert_eq!(tags.bad_tags().last(), Some(&("WRONG_NUM_CARS", 0)));
+    }
+
     #[test]
     fn test_get_global_train_predictions() {
         let data = get_test_data(String::from("global_train_predictions.json"));
@@ -954,4 +1325,139 @@ pub mod tests {
         let data = get_test_data(String::from("global_station_reports.json"));
         let _global_station_reports: StationReports = serde_json::from_str(data.as_str()).unwrap();
     }
+
+    #[rstest]
+    #[case::boarding("BRD", Eta::Boarding, "BRD")]
+    #[case::arriving("ARR", Eta::Arriving, "ARR")]
+    #[case::minutes("5", Eta::Minutes(5), "5m")]
+    #[case::scheduled("12:05", Eta::Scheduled { hour: 12, minute: 5 }, "12:05")]
+    #[case::delayed("DLY", Eta::Delayed, "DLY")]
+    #[case::unknown("?", Eta::Unknown, "?")]
+    #[case::no_prediction("---", Eta::Unknown, "?")]
+    fn test_parse_eta(#[case] min: &str, #[case] expected: Eta, #[case] display: &str) {
+        let eta = Eta::from(min);
+        assert_eq!(eta, expected);
+        assert_eq!(eta.to_string(), display);
+    }
+
+    #[test]
+    fn test_eta_ordering() {
+        let mut etas: Vec<Eta> = ["?", "1:05", "12:20", "10", "ARR", "2", "DLY", "BRD"]
+            .into_iter()
+            .map(Eta::from)
+            .collect();
+        etas.sort();
+        assert_eq!(
+            etas,
+            vec![
+                Eta::Boarding,
+                Eta::Arriving,
+                Eta::Minutes(2),
+                Eta::Minutes(10),
+                Eta::Scheduled {
+                    hour: 12,
+                    minute: 20
+                },
+                Eta::Scheduled { hour: 1, minute: 5 },
+                Eta::Delayed,
+                Eta::Unknown
+            ]
+        );
+    }
+
+    /// A departure from the test trip with the given ETA and minutes away.
+    fn departure(min: &str, minutes_away: Option<f64>) -> TrainPrediction {
+        let data = get_test_data(String::from("tripinfo_api.json"));
+        let mut trip_info: TripInfo = serde_json::from_str(data.as_str()).unwrap();
+        let mut train = trip_info.from_station_train_statuses.remove(0);
+        train.min = String::from(min);
+        train.minutes_away = minutes_away;
+        train
+    }
+
+    #[test]
+    fn test_cmp_departure_across_noon() {
+        let mut departures = [
+            departure("12:05", Some(20.0)),
+            departure("11:50", Some(5.0)),
+            departure("3", None),
+        ];
+        departures.sort_by(TrainPrediction::cmp_departure);
+        let etas: Vec<String> = departures.iter().map(|t| t.min.clone()).collect();
+        assert_eq!(etas, vec!["3", "11:50", "12:05"]);
+    }
+
+    #[test]
+    fn test_cmp_departure_interleaves_live_and_scheduled() {
+        let mut departures = [
+            departure("25", Some(25.0)),
+            departure("11:50", Some(5.0)),
+            departure("BRD", Some(0.0)),
+            departure("ARR", Some(0.0)),
+        ];
+        departures.sort_by(TrainPrediction::cmp_departure);
+        let etas: Vec<String> = departures.iter().map(|t| t.min.clone()).collect();
+        assert_eq!(etas, vec!["BRD", "ARR", "11:50", "25"]);
+    }
+
+    #[rstest]
+    #[case::code("RD", LineCode::Red)]
+    #[case::lowercase_code("bl", LineCode::Blue)]
+    #[case::name("Silver", LineCode::Silver)]
+    #[case::lowercase_name("orange", LineCode::Orange)]
+    #[case::non_revenue("N/A", LineCode::NonRevenue)]
+    fn test_parse_line_code(#[case] input: &str, #[case] expected: LineCode) {
+        assert_eq!(LineCode::from_str(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_parse_invalid_line_code() {
+        assert!(LineCode::from_str("Purple").is_err());
+    }
+
+    #[test]
+    fn test_line_code_round_trips() {
+        for line in LineCode::iter() {
+            assert_eq!(LineCode::from_str(&line.to_string()).unwrap(), line);
+            assert_eq!(LineCode::from_str(line.name()).unwrap(), line);
+        }
+        assert_eq!(LineCode::iter().count(), 7);
+    }
+
+    #[test]
+    fn test_line_colors() {
+        assert_eq!(LineCode::Blue.get_color(), Color::Blue);
+        assert_eq!(LineCode::Red.hex_color(), Some("#BF0D3E"));
+        assert_eq!(LineCode::NonRevenue.hex_color(), None);
+    }
+
+    #[test]
+    fn test_service_gap_severity() {
+        let data = get_test_data(String::from("system_metrics_api.json"));
+        let response: SystemMetricsResponse = serde_json::from_str(data.as_str()).unwrap();
+        let metrics = response.line_metrics_by_line.line(LineCode::Green).unwrap();
+        let severity = metrics.service_gaps[0].severity();
+        assert!((severity - 11.807).abs() < 0.001, "{severity}");
+    }
+
+    #[test]
+    fn test_system_metrics_by_line() {
+        let data = get_test_data(String::from("system_metrics_api.json"));
+        let response: SystemMetricsResponse = serde_json::from_str(data.as_str()).unwrap();
+        let metrics = response.line_metrics_by_line;
+
+        let lines: Vec<LineCode> = metrics.iter().map(|(line, _)| line).collect();
+        assert_eq!(lines.len(), 6);
+        for (line, line_metrics) in metrics.iter() {
+            assert_eq!(line_metrics.line_code, line);
+        }
+        assert!(metrics.line(LineCode::NonRevenue).is_none());
+
+        let red = metrics.line(LineCode::Red).unwrap();
+        for (direction, direction_metrics) in red.direction_metrics_by_direction.iter() {
+            assert_eq!(direction_metrics.direction_number, direction.number());
+        }
+        assert!(red.direction_metrics_by_direction.by_number(2).is_some());
+        assert!(red.direction_metrics_by_direction.by_number(3).is_none());
+    }
 }
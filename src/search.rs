@@ -0,0 +1,218 @@
+#![warn(missing_docs)]
+//! Fuzzy lookup of stations by name, tolerant of case, punctuation and typos.
+//!
+//! ```
+//! use metrohero_rs::StationCode;
+//!
+//! let matches = StationCode::search("metro ctr");
+//! assert_eq!(matches[0].station, StationCode::C01);
+//! ```
+use std::str::FromStr;
+
+use crate::schemas::{StationCode, STATION_NAME_TO_CODE};
+
+/// Matches scoring below this are not returned by [`search_stations`].
+pub const MIN_SCORE: f64 = 0.5;
+
+/// Matches scoring at least this are considered confident enough to use without confirmation.
+pub const CONFIDENT_SCORE: f64 = 0.85;
+
+/// Common names for stations which riders search for, but which are not official station names.
+const ALIASES: [(&str, StationCode); 2] = [
+    ("National Airport", StationCode::C10),
+    ("Reagan National Airport", StationCode::C10),
+];
+
+/// A station matching a search query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StationMatch {
+    /// The matching station.
+    pub station: StationCode,
+    /// The station name, or alias, which matched the query.
+    pub matched_name: &'static str,
+    /// How well the query matched, from 0 to 1. Exact matches score 1.
+    pub score: f64,
+}
+
+impl StationMatch {
+    /// Whether the match is close enough to use without asking the user to confirm it.
+    pub fn is_confident(&self) -> bool {
+        self.score >= CONFIDENT_SCORE
+    }
+}
+
+/// Search for stations by code or name, returning matches ranked best first.
+///
+/// Queries are compared case-insensitively and ignoring punctuation, so `"mt vernon sq"` matches
+/// `"Mt Vernon Sq 7th St-Convention Center"`. Words may be abbreviated or misspelled. Each station
+/// appears at most once, with its best-matching name.
+pub fn search_stations(query: &str) -> Vec<StationMatch> {
+    let query = normalize(query);
+    if query.is_empty() {
+        return vec![];
+    }
+
+    if let Ok(station) = StationCode::from_str(&query.to_uppercase()) {
+        if station != StationCode::UNKNOWN {
+            return vec![StationMatch {
+                station,
+                matched_name: station.to_name(),
+                score: 1.0,
+            }];
+        }
+    }
+
+    let mut matches: Vec<StationMatch> = STATION_NAME_TO_CODE
+        .iter()
+        .map(|(name, station)| (*name, *station))
+        .chain(ALIASES)
+        .filter(|(_, station)| *station != StationCode::UNKNOWN)
+        .map(|(name, station)| StationMatch {
+            station,
+            matched_name: name,
+            score: score(&query, &normalize(name)),
+        })
+        .filter(|m| m.score >= MIN_SCORE)
+        .collect();
+
+    // Best matches first, breaking ties by name so results are stable.
+    matches.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.matched_name.cmp(b.matched_name))
+    });
+    let mut seen = vec![];
+    matches.retain(|m| {
+        let first = !seen.contains(&m.station);
+        seen.push(m.station);
+        first
+    });
+    matches
+}
+
+/// Lowercase a name and replace runs of punctuation and whitespace with single spaces.
+fn normalize(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Score how well a normalized query matches a normalized name, from 0 to 1.
+fn score(query: &str, name: &str) -> f64 {
+    if query == name {
+        return 1.0;
+    }
+    if name.starts_with(query) {
+        return 0.95;
+    }
+
+    // Compare word by word, so queries naming part of a long station name still match.
+    let names: Vec<&str> = name.split(' ').collect();
+    let words: Vec<&str> = query.split(' ').collect();
+    let word_score = words
+        .iter()
+        .map(|word| {
+            names
+                .iter()
+                .map(|name| word_similarity(word, name))
+                .fold(0.0, f64::max)
+        })
+        .sum::<f64>()
+        / words.len() as f64;
+
+    // A little less than a prefix match, which covers all the query's words in order.
+    let word_score = word_score * 0.9;
+    word_score.max(similarity(query, name))
+}
+
+/// Similarity between two words, treating a word which abbreviates another as matching it.
+fn word_similarity(word: &str, name: &str) -> f64 {
+    if name.starts_with(word) {
+        1.0
+    } else {
+        similarity(word, name)
+    }
+}
+
+/// Similarity between two strings, from 0 to 1, based on their edit distance.
+fn similarity(a: &str, b: &str) -> f64 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f64 / longest as f64
+}
+
+/// The number of single character insertions, deletions and substitutions to turn `a` into `b`.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut distances: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut previous_diagonal = distances[0];
+        distances[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = previous_diagonal + usize::from(a_char != *b_char);
+            previous_diagonal = distances[j + 1];
+            distances[j + 1] = substitution
+                .min(distances[j] + 1)
+                .min(previous_diagonal + 1);
+        }
+    }
+    distances[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schemas::StationCode;
+    use crate::search::{levenshtein, normalize, search_stations};
+    use rstest::rstest;
+
+    #[rstest]
+    #[case::code("k04", StationCode::K04)]
+    #[case::lowercase("ballston", StationCode::K04)]
+    #[case::punctuation("mt. vernon sq", StationCode::E01)]
+    #[case::abbreviation("Metro Ctr", StationCode::C01)]
+    #[case::typo("Rosslin", StationCode::C05)]
+    #[case::partial("Dulles", StationCode::N10)]
+    #[case::word("navy yard", StationCode::F05)]
+    #[case::alias("national airport", StationCode::C10)]
+    #[case::existing_alias("u street", StationCode::E03)]
+    fn test_best_match(#[case] query: &str, #[case] expected: StationCode) {
+        let matches = search_stations(query);
+        assert_eq!(matches[0].station, expected, "{matches:?}");
+    }
+
+    #[test]
+    fn test_ambiguous_query() {
+        let matches = search_stations("farragut");
+        let confident: Vec<StationCode> = matches
+            .iter()
+            .filter(|m| m.is_confident())
+            .map(|m| m.station)
+            .collect();
+        assert_eq!(confident, vec![StationCode::A02, StationCode::C03]);
+    }
+
+    #[test]
+    fn test_no_matches() {
+        assert!(search_stations("xyzzy").is_empty());
+        assert!(search_stations("  ").is_empty());
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize("  Woodley Park-Zoo/Adams  Morgan "),
+            "woodley park zoo adams morgan"
+        );
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("same", "same"), 0);
+    }
+}
@@ -0,0 +1,347 @@
+#![warn(missing_docs)]
+//! Static metadata about each Metrorail station: where it is, which lines serve it, and what
+//! facilities it has.
+//!
+//! Street addresses are not included. WMATA's Rail Station Information API reports them for
+//! callers which need them.
+//!
+//! ```
+//! use metrohero_rs::{LineCode, StationCode};
+//! use metrohero_rs::stations::Jurisdiction;
+//!
+//! let info = StationCode::K04.info().unwrap();
+//! assert_eq!(info.name, "Ballston-MU");
+//! assert_eq!(info.jurisdiction, Jurisdiction::Virginia);
+//! assert_eq!(info.lines, vec![LineCode::Orange, LineCode::Silver]);
+//! ```
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Formatter;
+
+use serde::Serialize;
+
+use crate::network;
+use crate::schemas::{LineCode, StationCode};
+use Jurisdiction::*;
+use StationCode::*;
+
+/// The jurisdiction a station is located in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+pub enum Jurisdiction {
+    /// Washington, DC.
+    #[serde(rename = "DC")]
+    DistrictOfColumbia,
+    /// Maryland.
+    #[serde(rename = "MD")]
+    Maryland,
+    /// Virginia.
+    #[serde(rename = "VA")]
+    Virginia,
+}
+
+impl fmt::Display for Jurisdiction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DistrictOfColumbia => write!(f, "DC"),
+            Maryland => write!(f, "MD"),
+            Virginia => write!(f, "VA"),
+        }
+    }
+}
+
+/// Metadata about a Metrorail station.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StationInfo {
+    /// The station's RTU code.
+    pub code: StationCode,
+    /// The station's friendly name.
+    pub name: &'static str,
+    /// The latitude of the station, in degrees.
+    pub latitude: f64,
+    /// The longitude of the station, in degrees.
+    pub longitude: f64,
+    /// The lines which stop at the station's platform.
+    pub lines: Vec<LineCode>,
+    /// The jurisdiction the station is located in.
+    pub jurisdiction: Jurisdiction,
+    /// The code for the station's other platform, for stations with separate platforms for
+    /// different lines, e.g. [`StationCode::C01`] for [`StationCode::A01`].
+    pub other_platform: Option<StationCode>,
+    /// Whether Metro operates parking at the station.
+    pub parking: bool,
+}
+
+impl StationInfo {
+    /// The lines which stop at this station, including at its other platform.
+    pub fn all_lines(&self) -> Vec<LineCode> {
+        let mut lines = self.lines.clone();
+        if let Some(other_platform) = self.other_platform {
+            lines.extend(network::lines_serving(other_platform));
+        }
+        lines
+    }
+
+    /// The codes of this station's platforms, starting with [`code`](Self::code).
+    pub fn platform_codes(&self) -> Vec<StationCode> {
+        let mut codes = vec![self.code];
+        codes.extend(self.other_platform);
+        codes
+    }
+}
+
+/// Coordinates, jurisdiction and parking for each station.
+const STATIONS: [(StationCode, f64, f64, Jurisdiction, bool); 102] = [
+    (A01, 38.898303, -77.028099, DistrictOfColumbia, false),
+    (A02, 38.903192, -77.039766, DistrictOfColumbia, false),
+    (A03, 38.909499, -77.043620, DistrictOfColumbia, false),
+    (A04, 38.924999, -77.052648, DistrictOfColumbia, false),
+    (A05, 38.934703, -77.058226, DistrictOfColumbia, false),
+    (A06, 38.943620, -77.063511, DistrictOfColumbia, false),
+    (A07, 38.947808, -77.079615, DistrictOfColumbia, false),
+    (A08, 38.960744, -77.085969, DistrictOfColumbia, false),
+    (A09, 38.984282, -77.094431, Maryland, false),
+    (A10, 38.999947, -77.097253, Maryland, false),
+    (A11, 39.029158, -77.104150, Maryland, true),
+    (A12, 39.048043, -77.113131, Maryland, true),
+    (A13, 39.062359, -77.121113, Maryland, true),
+    (A14, 39.084215, -77.146424, Maryland, true),
+    (A15, 39.119819, -77.164921, Maryland, true),
+    (B01, 38.898340, -77.021851, DistrictOfColumbia, false),
+    (B02, 38.896084, -77.016643, DistrictOfColumbia, false),
+    (B03, 38.897723, -77.006745, DistrictOfColumbia, false),
+    (B04, 38.920741, -76.995984, DistrictOfColumbia, true),
+    (B05, 38.933234, -76.994544, DistrictOfColumbia, false),
+    (B06, 38.951777, -77.002174, DistrictOfColumbia, true),
+    (B07, 38.975532, -77.017348, DistrictOfColumbia, true),
+    (B08, 38.993841, -77.031321, Maryland, false),
+    (B09, 39.015413, -77.042953, Maryland, true),
+    (B10, 39.038558, -77.051098, Maryland, true),
+    (B11, 39.061713, -77.053410, Maryland, true),
+    (B35, 38.907407, -77.002961, DistrictOfColumbia, false),
+    (C01, 38.898303, -77.028099, DistrictOfColumbia, false),
+    (C02, 38.901316, -77.033652, DistrictOfColumbia, false),
+    (C03, 38.901311, -77.039810, DistrictOfColumbia, false),
+    (C04, 38.900599, -77.050273, DistrictOfColumbia, false),
+    (C05, 38.896595, -77.071460, Virginia, false),
+    (C06, 38.884574, -77.063108, Virginia, false),
+    (C07, 38.869349, -77.054013, Virginia, false),
+    (C08, 38.863045, -77.059507, Virginia, false),
+    (C09, 38.857790, -77.050589, Virginia, false),
+    (C10, 38.852985, -77.043805, Virginia, false),
+    (C11, 38.833350, -77.046390, Virginia, false),
+    (C12, 38.814009, -77.053763, Virginia, false),
+    (C13, 38.806474, -77.061115, Virginia, false),
+    (C14, 38.800313, -77.071173, Virginia, true),
+    (C15, 38.793841, -77.075301, Virginia, true),
+    (D01, 38.893757, -77.028218, DistrictOfColumbia, false),
+    (D02, 38.888022, -77.028232, DistrictOfColumbia, false),
+    (D03, 38.884775, -77.021964, DistrictOfColumbia, false),
+    (D04, 38.884958, -77.015860, DistrictOfColumbia, false),
+    (D05, 38.884968, -77.005137, DistrictOfColumbia, false),
+    (D06, 38.884124, -76.995334, DistrictOfColumbia, false),
+    (D07, 38.880841, -76.985721, DistrictOfColumbia, false),
+    (D08, 38.886713, -76.977485, DistrictOfColumbia, false),
+    (D09, 38.898284, -76.948042, DistrictOfColumbia, true),
+    (D10, 38.907734, -76.936177, DistrictOfColumbia, true),
+    (D11, 38.916520, -76.915427, Maryland, true),
+    (D12, 38.934411, -76.890988, Maryland, true),
+    (D13, 38.947674, -76.872144, Maryland, true),
+    (E01, 38.905604, -77.022256, DistrictOfColumbia, false),
+    (E02, 38.914041, -77.021800, DistrictOfColumbia, false),
+    (E03, 38.916489, -77.028938, DistrictOfColumbia, false),
+    (E04, 38.928672, -77.032104, DistrictOfColumbia, false),
+    (E05, 38.936077, -77.024135, DistrictOfColumbia, false),
+    (E06, 38.951777, -77.002174, DistrictOfColumbia, true),
+    (E07, 38.954931, -76.969881, Maryland, true),
+    (E08, 38.965276, -76.956182, Maryland, true),
+    (E09, 38.978523, -76.928432, Maryland, true),
+    (E10, 39.011036, -76.911362, Maryland, true),
+    (F01, 38.898340, -77.021851, DistrictOfColumbia, false),
+    (F02, 38.893893, -77.021902, DistrictOfColumbia, false),
+    (F03, 38.884775, -77.021964, DistrictOfColumbia, false),
+    (F04, 38.876221, -77.017491, DistrictOfColumbia, false),
+    (F05, 38.876588, -77.005086, DistrictOfColumbia, false),
+    (F06, 38.862072, -76.995648, DistrictOfColumbia, true),
+    (F07, 38.845334, -76.988170, DistrictOfColumbia, false),
+    (F08, 38.840974, -76.975360, Maryland, true),
+    (F09, 38.851187, -76.956565, Maryland, true),
+    (F10, 38.843891, -76.932022, Maryland, true),
+    (F11, 38.826995, -76.912134, Maryland, true),
+    (G01, 38.890488, -76.938291, DistrictOfColumbia, false),
+    (G02, 38.889757, -76.913382, Maryland, true),
+    (G03, 38.886373, -76.893592, Maryland, true),
+    (G04, 38.893800, -76.868000, Maryland, true),
+    (G05, 38.900800, -76.844600, Maryland, true),
+    (J02, 38.799193, -77.129407, Virginia, true),
+    (J03, 38.766129, -77.168797, Virginia, true),
+    (K01, 38.890475, -77.087055, Virginia, false),
+    (K02, 38.886704, -77.095887, Virginia, false),
+    (K03, 38.883310, -77.104267, Virginia, false),
+    (K04, 38.882071, -77.111845, Virginia, false),
+    (K05, 38.885841, -77.157177, Virginia, true),
+    (K06, 38.900670, -77.189394, Virginia, true),
+    (K07, 38.883015, -77.228939, Virginia, true),
+    (K08, 38.877693, -77.271562, Virginia, true),
+    (N01, 38.924432, -77.210295, Virginia, false),
+    (N02, 38.920496, -77.223753, Virginia, false),
+    (N03, 38.921732, -77.234607, Virginia, false),
+    (N04, 38.928837, -77.241674, Virginia, false),
+    (N06, 38.947753, -77.340179, Virginia, true),
+    (N07, 38.952570, -77.360270, Virginia, false),
+    (N08, 38.953100, -77.385000, Virginia, true),
+    (N09, 38.960300, -77.415600, Virginia, true),
+    (N10, 38.955600, -77.448000, Virginia, false),
+    (N11, 38.991700, -77.460600, Virginia, true),
+    (N12, 39.005400, -77.491200, Virginia, true),
+];
+
+lazy_static! {
+    static ref STATION_INFO: HashMap<StationCode, StationInfo> = STATIONS
+        .iter()
+        .map(|(code, latitude, longitude, jurisdiction, parking)| {
+            let info = StationInfo {
+                code: *code,
+                name: code.to_name(),
+                latitude: *latitude,
+                longitude: *longitude,
+                lines: network::lines_serving(*code),
+                jurisdiction: *jurisdiction,
+                other_platform: network::other_platform(*code),
+                parking: *parking,
+            };
+            (*code, info)
+        })
+        .collect();
+}
+
+/// A station near a location, found by [`nearest_stations`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NearbyStation {
+    /// Metadata about the station.
+    pub info: &'static StationInfo,
+    /// The straight-line distance from the location to the station, in meters.
+    pub distance_meters: f64,
+}
+
+/// The mean radius of the Earth, in meters.
+const EARTH_RADIUS_METERS: f64 = 6_371_008.8;
+
+/// Find the `n` stations closest to a location, nearest first.
+///
+/// Stations with separate platforms for different lines, such as Metro Center, are listed once,
+/// under the platform with the lowest code. Use [`StationInfo::all_lines`] for the lines serving
+/// both platforms.
+pub fn nearest_stations(latitude: f64, longitude: f64, n: usize) -> Vec<NearbyStation> {
+    let mut stations: Vec<NearbyStation> = STATION_INFO
+        .values()
+        .filter(|info| {
+            info.other_platform
+                .is_none_or(|other| info.code.to_string() < other.to_string())
+        })
+        .map(|info| NearbyStation {
+            info,
+            distance_meters: distance_meters(latitude, longitude, info.latitude, info.longitude),
+        })
+        .collect();
+    stations.sort_by(|a, b| {
+        a.distance_meters
+            .total_cmp(&b.distance_meters)
+            .then_with(|| a.info.code.to_string().cmp(&b.info.code.to_string()))
+    });
+    stations.truncate(n);
+    stations
+}
+
+/// The great-circle distance between two points, in meters, using the haversine formula.
+pub fn distance_meters(latitude1: f64, longitude1: f64, latitude2: f64, longitude2: f64) -> f64 {
+    let (phi1, phi2) = (latitude1.to_radians(), latitude2.to_radians());
+    let delta_phi = (latitude2 - latitude1).to_radians();
+    let delta_lambda = (longitude2 - longitude1).to_radians();
+    let a = (delta_phi / 2.0).sin().powi(2)
+        + phi1.cos() * phi2.cos() * (delta_lambda / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_METERS * a.sqrt().asin()
+}
+
+/// Get metadata about a station, or `None` for [`StationCode::UNKNOWN`].
+pub fn station_info(station: StationCode) -> Option<&'static StationInfo> {
+    STATION_INFO.get(&station)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::schemas::{LineCode, StationCode};
+    use crate::stations::{distance_meters, nearest_stations, station_info, Jurisdiction};
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_every_station_has_info() {
+        for station in StationCode::iter().filter(|s| *s != StationCode::UNKNOWN) {
+            let info = station_info(station).unwrap();
+            assert_eq!(info.code, station);
+            assert!((38.7..39.2).contains(&info.latitude), "{station}");
+            assert!((-77.5..-76.8).contains(&info.longitude), "{station}");
+        }
+        assert!(station_info(StationCode::UNKNOWN).is_none());
+    }
+
+    #[test]
+    fn test_station_complex() {
+        let metro_center = station_info(StationCode::A01).unwrap();
+        let other_platform = station_info(metro_center.other_platform.unwrap()).unwrap();
+        assert_eq!(other_platform.code, StationCode::C01);
+        assert_eq!(metro_center.latitude, other_platform.latitude);
+        assert_eq!(metro_center.lines, vec![LineCode::Red]);
+        assert_eq!(metro_center.all_lines().len(), 4);
+        assert_eq!(
+            metro_center.platform_codes(),
+            vec![StationCode::A01, StationCode::C01]
+        );
+        assert_eq!(other_platform.platform_codes()[0], StationCode::C01);
+    }
+
+    #[test]
+    fn test_jurisdiction_and_parking() {
+        let shady_grove = station_info(StationCode::A15).unwrap();
+        assert_eq!(shady_grove.jurisdiction, Jurisdiction::Maryland);
+        assert!(shady_grove.parking);
+        assert_eq!(
+            station_info(StationCode::C05).unwrap().jurisdiction,
+            Jurisdiction::Virginia
+        );
+        assert!(!station_info(StationCode::A01).unwrap().parking);
+    }
+
+    #[test]
+    fn test_nearest_stations() {
+        // The Washington Monument.
+        let nearest = nearest_stations(38.8895, -77.0353, 3);
+        let codes: Vec<StationCode> = nearest.iter().map(|s| s.info.code).collect();
+        assert_eq!(
+            codes,
+            vec![StationCode::D02, StationCode::D01, StationCode::A01]
+        );
+        assert!(nearest[0].distance_meters < 1_000.0);
+        assert!(nearest[0].distance_meters <= nearest[1].distance_meters);
+    }
+
+    #[test]
+    fn test_nearest_stations_lists_complexes_once() {
+        // Metro Center, which has platforms A01 and C01.
+        let nearest = nearest_stations(38.898303, -77.028099, 4);
+        let codes: Vec<StationCode> = nearest.iter().map(|s| s.info.code).collect();
+        assert_eq!(codes[0], StationCode::A01);
+        assert!(!codes.contains(&StationCode::C01));
+        assert!(!codes.contains(&StationCode::F01), "{codes:?}");
+        assert_eq!(nearest[0].info.all_lines().len(), 4);
+    }
+
+    #[test]
+    fn test_distance_meters() {
+        // Metro Center to Shady Grove is about 27km as the crow flies.
+        let distance = distance_meters(38.898303, -77.028099, 39.119819, -77.164921);
+        assert!((26_000.0..28_000.0).contains(&distance), "{distance}");
+        assert_eq!(distance_meters(38.9, -77.0, 38.9, -77.0), 0.0);
+    }
+}
@@ -0,0 +1,441 @@
+#![warn(missing_docs)]
+//! Transports which carry requests from a [`MetroHeroClient`](crate::MetroHeroClient) to the
+//! MetroHero API, or to a stand-in for it.
+//!
+//! By default, clients send requests over HTTP with [`ReqwestTransport`]. Code which uses a client
+//! can be tested without network access by building the client with an [`InMemoryTransport`]
+//! serving canned responses instead, or with a [`ReplayTransport`] serving fixtures captured from
+//! a real session by a [`RecordingTransport`].
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::{fs, io};
+
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+
+use crate::errors::MetroHeroError;
+
+/// A raw, unparsed response from the MetroHero API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawResponse {
+    /// The HTTP status code of the response.
+    pub status: u16,
+    /// The body of the response.
+    pub body: String,
+}
+
+impl RawResponse {
+    /// A successful response with the given body.
+    pub fn ok(body: impl Into<String>) -> Self {
+        Self {
+            status: 200,
+            body: body.into(),
+        }
+    }
+}
+
+/// Sends requests on behalf of a [`MetroHeroClient`](crate::MetroHeroClient).
+///
+/// Transports only carry requests. Interpreting status codes, retries, rate limiting and caching
+/// are handled by the client, so they behave identically regardless of the transport in use.
+pub trait Transport: Send + Sync + Debug {
+    /// Send a GET request for `request_path`, e.g. `/metrorail/stations/K04/tags`.
+    ///
+    /// Paths are relative to the API's base URL and may include a query string.
+    fn get(&self, request_path: &str) -> Result<RawResponse, MetroHeroError>;
+}
+
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn get(&self, request_path: &str) -> Result<RawResponse, MetroHeroError> {
+        (**self).get(request_path)
+    }
+}
+
+/// Sends requests to the MetroHero API over HTTP. This is the default transport.
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    http_client: Client,
+    api_url_base: String,
+}
+
+impl ReqwestTransport {
+    /// Create a transport sending requests with `http_client` to the API at `api_url_base`.
+    ///
+    /// The HTTP client is responsible for authentication, e.g. by sending the `apiKey` header.
+    pub fn new(http_client: Client, api_url_base: impl Into<String>) -> Self {
+        Self {
+            http_client,
+            api_url_base: api_url_base.into(),
+        }
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn get(&self, request_path: &str) -> Result<RawResponse, MetroHeroError> {
+        let request_url = format!("{}{}", self.api_url_base, request_path);
+        let response = self
+            .http_client
+            .get(request_url)
+            .send()
+            .map_err(|e| MetroHeroError::http(request_path, e))?;
+        let status = response.status().as_u16();
+        let body = response
+            .text()
+            .map_err(|e| MetroHeroError::http(request_path, e))?;
+        Ok(RawResponse { status, body })
+    }
+}
+
+/// Serves canned responses by request path, without any network access.
+///
+/// Requests for paths without a canned response receive a 404 response.
+///
+/// # Example
+///
+/// ```
+/// use metrohero_rs::{MetroHeroClient, MetroHeroError, StationCode};
+/// use metrohero_rs::transport::{InMemoryTransport, RawResponse};
+///
+/// let transport = InMemoryTransport::new()
+///     .with_json("/metrorail/tweets", "[]")
+///     .with_response("/metrorail/stations/UNKNOWN/tags", RawResponse { status: 400, body: String::new() });
+///
+/// let client = MetroHeroClient::builder("YOUR_API_KEY".to_string())
+///     .transport(transport)
+///     .build()
+///     .unwrap();
+///
+/// assert!(client.get_tweets().unwrap().is_empty());
+/// assert_eq!(
+///     client.get_station_report(&StationCode::UNKNOWN).unwrap_err(),
+///     MetroHeroError::InvalidStation {
+///         request_path: Some("/metrorail/stations/UNKNOWN/tags".to_string())
+///     }
+/// );
+/// ```
+#[derive(Debug, Default)]
+pub struct InMemoryTransport {
+    responses: Mutex<HashMap<String, RawResponse>>,
+    requests: Mutex<Vec<String>>,
+}
+
+impl InMemoryTransport {
+    /// Create a transport without any canned responses.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serve `body` with a 200 status for requests to `request_path`.
+    pub fn with_json(self, request_path: impl Into<String>, body: impl Into<String>) -> Self {
+        self.with_response(request_path, RawResponse::ok(body))
+    }
+
+    /// Serve `response` for requests to `request_path`.
+    pub fn with_response(self, request_path: impl Into<String>, response: RawResponse) -> Self {
+        self.insert(request_path, response);
+        self
+    }
+
+    /// Serve `response` for requests to `request_path`, replacing any existing response.
+    pub fn insert(&self, request_path: impl Into<String>, response: RawResponse) {
+        self.responses
+            .lock()
+            .unwrap()
+            .insert(request_path.into(), response);
+    }
+
+    /// The paths of all requests received so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Transport for InMemoryTransport {
+    fn get(&self, request_path: &str) -> Result<RawResponse, MetroHeroError> {
+        self.requests.lock().unwrap().push(request_path.to_string());
+        let response = self.responses.lock().unwrap().get(request_path).cloned();
+        Ok(response.unwrap_or_else(|| RawResponse {
+            status: 404,
+            body: format!("No canned response for {request_path}"),
+        }))
+    }
+}
+
+/// The name of the file, within a fixture directory, which maps request paths to fixtures.
+pub const FIXTURE_INDEX: &str = "index.json";
+
+/// An entry in a fixture directory's index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct FixtureEntry {
+    /// The file, relative to the fixture directory, holding the raw response body.
+    file: String,
+    /// The HTTP status code of the response.
+    status: u16,
+}
+
+/// Read the index of a fixture directory, or an empty index if there is none yet.
+fn read_fixture_index(dir: &Path) -> Result<BTreeMap<String, FixtureEntry>, MetroHeroError> {
+    let index_path = dir.join(FIXTURE_INDEX);
+    if !index_path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let index =
+        fs::read_to_string(&index_path).map_err(|e| MetroHeroError::fixture(&index_path, e))?;
+    Ok(serde_json::from_str(&index)?)
+}
+
+/// Name the fixture file for a request path, e.g. `metrorail_stations_K04_tags.json`.
+fn fixture_file_name(request_path: &str) -> String {
+    let name: String = request_path
+        .trim_start_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    format!("{name}.json")
+}
+
+/// Records every response received through another transport into a fixture directory.
+///
+/// Each response body is written verbatim to its own file, and the directory's `index.json` maps
+/// request paths to those files. Recording into a directory which already holds fixtures
+/// refreshes them. Recorded fixtures can be served back with a [`ReplayTransport`].
+///
+/// # Example
+///
+/// ```no_run
+/// use metrohero_rs::MetroHeroClient;
+/// use metrohero_rs::transport::RecordingTransport;
+///
+/// let live = MetroHeroClient::new("YOUR_API_KEY".to_string());
+/// let client = MetroHeroClient::builder("YOUR_API_KEY".to_string())
+///     .transport(RecordingTransport::new(live.transport(), "tests/data/recorded").unwrap())
+///     .build()
+///     .unwrap();
+/// client.get_tweets().unwrap(); // Written to tests/data/recorded/metrorail_tweets.json
+/// ```
+#[derive(Debug)]
+pub struct RecordingTransport<T: Transport> {
+    inner: T,
+    dir: PathBuf,
+    index: Mutex<BTreeMap<String, FixtureEntry>>,
+}
+
+impl<T: Transport> RecordingTransport<T> {
+    /// Record responses received through `inner` into the directory `dir`, creating it if needed.
+    pub fn new(inner: T, dir: impl Into<PathBuf>) -> Result<Self, MetroHeroError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir).map_err(|e| MetroHeroError::fixture(&dir, e))?;
+        let index = read_fixture_index(&dir)?;
+        Ok(Self {
+            inner,
+            dir,
+            index: Mutex::new(index),
+        })
+    }
+
+    /// Write a response and the updated index to the fixture directory.
+    fn record(&self, request_path: &str, response: &RawResponse) -> Result<(), MetroHeroError> {
+        let mut index = self.index.lock().unwrap();
+        let file = match index.get(request_path) {
+            Some(entry) => entry.file.clone(),
+            None => fixture_file_name(request_path),
+        };
+
+        let body_path = self.dir.join(&file);
+        fs::write(&body_path, &response.body)
+            .map_err(|e| MetroHeroError::fixture(&body_path, e))?;
+
+        index.insert(
+            request_path.to_string(),
+            FixtureEntry {
+                file,
+                status: response.status,
+            },
+        );
+        let index_path = self.dir.join(FIXTURE_INDEX);
+        let index_json = serde_json::to_string_pretty(&*index)?;
+        fs::write(&index_path, index_json + "\n")
+            .map_err(|e| MetroHeroError::fixture(&index_path, e))
+    }
+}
+
+impl<T: Transport> Transport for RecordingTransport<T> {
+    fn get(&self, request_path: &str) -> Result<RawResponse, MetroHeroError> {
+        let response = self.inner.get(request_path)?;
+        self.record(request_path, &response)?;
+        Ok(response)
+    }
+}
+
+/// Serves responses from a fixture directory written by a [`RecordingTransport`].
+///
+/// Requests for paths without a fixture receive a 404 response.
+#[derive(Debug)]
+pub struct ReplayTransport {
+    fixtures: InMemoryTransport,
+}
+
+impl ReplayTransport {
+    /// Load all fixtures listed in the `index.json` of the directory `dir`.
+    pub fn open(dir: impl AsRef<Path>) -> Result<Self, MetroHeroError> {
+        let dir = dir.as_ref();
+        let index_path = dir.join(FIXTURE_INDEX);
+        if !index_path.exists() {
+            let error = io::Error::new(io::ErrorKind::NotFound, "fixture index not found");
+            return Err(MetroHeroError::fixture(&index_path, error));
+        }
+
+        let fixtures = InMemoryTransport::new();
+        for (request_path, entry) in read_fixture_index(dir)? {
+            let body_path = dir.join(&entry.file);
+            let body = fs::read_to_string(&body_path)
+                .map_err(|e| MetroHeroError::fixture(&body_path, e))?;
+            fixtures.insert(
+                request_path,
+                RawResponse {
+                    status: entry.status,
+                    body,
+                },
+            );
+        }
+        Ok(Self { fixtures })
+    }
+
+    /// The paths of all requests received so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.fixtures.requests()
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn get(&self, request_path: &str) -> Result<RawResponse, MetroHeroError> {
+        self.fixtures.get(request_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::MetroHeroClient;
+    use crate::errors::MetroHeroError;
+    use crate::schemas::tests::get_test_data;
+    use crate::schemas::StationCode;
+    use crate::transport::{
+        InMemoryTransport, RawResponse, RecordingTransport, ReplayTransport, Transport,
+    };
+    use std::sync::Arc;
+    use std::{env, fs};
+
+    #[test]
+    fn test_in_memory_transport() {
+        let transport = Arc::new(InMemoryTransport::new().with_json(
+            "/metrorail/trips/K03/C02",
+            get_test_data(String::from("tripinfo_api.json")),
+        ));
+        let client = MetroHeroClient::builder(String::from("METROHERO_API_KEY"))
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+
+        let trip_info = client
+            .get_trip_info(&StationCode::K03, &StationCode::C02)
+            .unwrap();
+        assert_eq!(trip_info.to_station_code, StationCode::C02);
+        assert_eq!(transport.requests(), vec!["/metrorail/trips/K03/C02"]);
+    }
+
+    #[test]
+    fn test_in_memory_transport_unknown_path() {
+        let transport = InMemoryTransport::new();
+        assert_eq!(transport.get("/metrorail/tweets").unwrap().status, 404);
+
+        let client = MetroHeroClient::builder(String::from("METROHERO_API_KEY"))
+            .transport(transport)
+            .build()
+            .unwrap();
+        let err = client.get_tweets().unwrap_err();
+        assert!(matches!(
+            err,
+            MetroHeroError::UnexpectedStatus { status: 404, .. }
+        ));
+    }
+
+    #[test]
+    fn test_in_memory_transport_error_status() {
+        let transport = InMemoryTransport::new().with_response(
+            "/metrorail/trains/123/tags",
+            RawResponse {
+                status: 400,
+                body: String::new(),
+            },
+        );
+        let client = MetroHeroClient::builder(String::from("METROHERO_API_KEY"))
+            .transport(transport)
+            .build()
+            .unwrap();
+        let err = client.get_train_report(String::from("123")).unwrap_err();
+        assert_eq!(
+            err,
+            MetroHeroError::InvalidTrainId {
+                request_path: Some(String::from("/metrorail/trains/123/tags"))
+            }
+        );
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let dir = env::temp_dir().join(format!("metrohero_fixtures_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let live = InMemoryTransport::new()
+            .with_json(
+                "/metrorail/tweets",
+                get_test_data(String::from("tweets_api.json")),
+            )
+            .with_response(
+                "/metrorail/stations/UNKNOWN/tags",
+                RawResponse {
+                    status: 400,
+                    body: String::from("Bad station"),
+                },
+            );
+        let recorder = RecordingTransport::new(live, &dir).unwrap();
+        recorder.get("/metrorail/tweets").unwrap();
+        recorder.get("/metrorail/stations/UNKNOWN/tags").unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("metrorail_tweets.json")).unwrap(),
+            get_test_data(String::from("tweets_api.json"))
+        );
+
+        let client = MetroHeroClient::builder(String::from("METROHERO_API_KEY"))
+            .transport(ReplayTransport::open(&dir).unwrap())
+            .build()
+            .unwrap();
+        client.get_tweets().unwrap();
+        let err = client
+            .get_station_report(&StationCode::UNKNOWN)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            MetroHeroError::InvalidStation {
+                request_path: Some(String::from("/metrorail/stations/UNKNOWN/tags"))
+            }
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_replay_missing_index() {
+        let dir = env::temp_dir().join("metrohero_fixtures_missing");
+        let err = ReplayTransport::open(&dir).unwrap_err();
+        assert_eq!(
+            err,
+            MetroHeroError::FixtureError {
+                path: dir.join("index.json"),
+                source: std::io::ErrorKind::NotFound.into()
+            }
+        );
+    }
+}
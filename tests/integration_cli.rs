@@ -1,7 +1,13 @@
 use assert_cmd::Command;
 
+/// Get a command for the CLI, serving API responses from the fixtures in `tests/data/cli`.
 fn get_cmd() -> Command {
-    return Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    cmd.args(vec!["--api-key", "METROHERO_API_KEY"]).env(
+        "METROHERO_REPLAY_DIR",
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/cli"),
+    );
+    cmd
 }
 
 #[test]
@@ -11,10 +17,7 @@ fn get_trip_info_by_code() {
     let output_text = String::from_utf8(output.stdout).unwrap();
     let std_err = String::from_utf8(output.stderr).unwrap();
     println!("{}", std_err);
-    assert_eq!(
-        output_text.contains("Virginia Square-GMU --> McPherson Square"),
-        true
-    );
+    assert!(output_text.contains("Virginia Square-GMU --> McPherson Square"));
 }
 
 #[test]
@@ -27,10 +30,237 @@ fn get_trip_info_by_name() {
     let output_text = String::from_utf8(output.stdout).unwrap();
     let std_err = String::from_utf8(output.stderr).unwrap();
     println!("{}", std_err);
-    assert_eq!(
-        output_text.contains("Virginia Square-GMU --> McPherson Square"),
-        true
-    );
+    assert!(output_text.contains("Virginia Square-GMU --> McPherson Square"));
+}
+
+#[test]
+fn plan_falls_back_to_offline_route() {
+    let mut cmd = get_cmd();
+    let output = cmd.args(vec!["plan", "K04", "A15"]).output().unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success());
+    assert!(output_text.contains("Ballston-MU --> Shady Grove"));
+    assert!(output_text.contains("OR/SV"));
+    assert!(output_text.contains("planned offline"));
+}
+
+#[test]
+fn get_trip_info_shows_outages() {
+    let mut cmd = get_cmd();
+    let output = cmd.args(vec!["plan", "K03", "C02"]).output().unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert!(output_text.contains("Elevator and escalator outages at McPherson Square"));
+    assert!(output_text.contains("Escalator between street and mezzanine"));
+}
+
+#[test]
+fn get_outages() {
+    // McPherson Square's outages are reported at the origin of a trip to Metro Center.
+    let mut cmd = get_cmd();
+    let output = cmd
+        .args(vec!["outages", "McPherson Square"])
+        .output()
+        .unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert!(output_text.contains("Elevator and escalator outages at McPherson Square (C02)"));
+    assert!(output_text.contains("Inspection Repair"));
+}
+
+#[test]
+fn get_tweets_newest_first() {
+    let mut cmd = get_cmd();
+    let output = cmd.args(vec!["tweets"]).output().unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    let newest = output_text.find("Shuttle buses will replace").unwrap();
+    let oldest = output_text.find("Train 603").unwrap();
+    assert!(newest < oldest);
+    assert!(output_text.contains("https://twitter.com/1522464770/status/1591918817273434112"));
+}
+
+#[test]
+fn get_tweets_filtered() {
+    let mut cmd = get_cmd();
+    let output = cmd
+        .args(vec![
+            "tweets",
+            "--station",
+            "Virginia Square",
+            "--keyword",
+            "holding",
+        ])
+        .output()
+        .unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output_text.matches("Train 607").count(), 2);
+    assert!(!output_text.contains("Train 604"));
+
+    let mut cmd = get_cmd();
+    let output = cmd.args(vec!["tweets", "--line", "GR"]).output().unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert!(output_text.contains("No tweets found."));
+}
+
+#[test]
+fn get_alerts_for_line() {
+    // Alerts for a line come from a trip between its terminals.
+    let mut cmd = get_cmd();
+    let output = cmd.args(vec!["alerts", "--line", "YL"]).output().unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    let newest = output_text.find("express shuttles").unwrap();
+    let oldest = output_text.find("Until May 2023").unwrap();
+    assert!(newest < oldest);
+
+    let mut cmd = get_cmd();
+    let output = cmd
+        .args(vec!["alerts", "--line", "YL", "--keyword", "shuttles"])
+        .output()
+        .unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert!(output_text.contains("express shuttles"));
+    assert!(!output_text.contains("Until May 2023"));
+}
+
+#[test]
+fn get_alerts_for_station() {
+    // Alerts for a station come from a trip to a neighboring station.
+    let mut cmd = get_cmd();
+    let output = cmd
+        .args(vec!["alerts", "--station", "McPherson Square"])
+        .output()
+        .unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert!(output_text.contains("express shuttles"));
+    assert!(output_text.contains("Until May 2023"));
+}
+
+#[test]
+fn get_alerts_requires_line_or_station() {
+    let mut cmd = get_cmd();
+    let output = cmd.args(vec!["alerts"]).output().unwrap();
+    assert!(!output.status.success());
+    let error_text = String::from_utf8(output.stderr).unwrap();
+    assert!(error_text.contains("use --line or --station"));
+}
+
+#[test]
+fn get_trains_for_line() {
+    let mut cmd = get_cmd();
+    let output = cmd
+        .args(vec!["trains", "--line", "RD", "--direction", "2"])
+        .output()
+        .unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert!(output_text.contains("White Flint"));
+    assert!(!output_text.contains("Cleveland Park"));
+    assert!(!output_text.contains("Minnesota Avenue"));
+}
+
+#[test]
+fn get_trains_by_delay() {
+    let mut cmd = get_cmd();
+    let output = cmd
+        .args(vec!["trains", "--line", "OR", "--by-delay"])
+        .output()
+        .unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    let most_delayed = output_text.find("Stadium-Armory").unwrap();
+    let least_delayed = output_text.find("Ballston").unwrap();
+    assert!(most_delayed < least_delayed);
+    assert!(output_text.contains("+9m"));
+    assert!(output_text.contains("Holding"));
+}
+
+#[test]
+fn get_train() {
+    let mut cmd = get_cmd();
+    let output = cmd.args(vec!["train", "070"]).output().unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert!(output_text.contains("White Flint (from Grosvenor-Strathmore)"));
+    assert!(output_text.contains("Shady Grove"));
+    assert!(output_text.contains("CROWDED"));
+    assert!(!output_text.contains("GOOD_RIDE"));
+}
+
+#[test]
+fn get_train_invalid_id() {
+    let mut cmd = get_cmd();
+    let output = cmd.args(vec!["train", "999"]).output().unwrap();
+    assert!(!output.status.success());
+    let error_text = String::from_utf8(output.stderr).unwrap();
+    assert!(error_text.contains("Provided Train ID is not valid"));
+}
+
+#[test]
+fn get_departures_as_json() {
+    let mut cmd = get_cmd();
+    let output = cmd
+        .args(vec!["--output", "json", "departures", "K03"])
+        .output()
+        .unwrap();
+    let records: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let records = records.as_array().unwrap();
+    assert!(!records.is_empty());
+    for field in ["Line", "DestinationName", "Min", "minutesAway", "Car"] {
+        assert!(records[0].get(field).is_some(), "missing {field}");
+    }
+}
+
+#[test]
+fn get_trip_info_as_csv() {
+    let mut cmd = get_cmd();
+    let output = cmd
+        .args(vec!["plan", "K03", "C02", "--output", "csv"])
+        .output()
+        .unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = output_text.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("from_station_code,from_station_name,to_station_code"));
+    assert!(lines[1].starts_with("K03,Virginia Square-GMU,C02,McPherson Square,"));
+    assert!(lines[1].ends_with(",2,1"));
+}
+
+#[test]
+fn get_trains_as_ndjson() {
+    let mut cmd = get_cmd();
+    let output = cmd
+        .args(vec!["--output", "ndjson", "trains", "--line", "RD"])
+        .output()
+        .unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(output_text.lines().count(), 12);
+    for line in output_text.lines() {
+        let record: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert_eq!(record["Line"], "RD");
+    }
+}
+
+#[test]
+fn get_gaps_as_yaml() {
+    let mut cmd = get_cmd();
+    let output = cmd.args(vec!["--output", "yaml", "gaps"]).output().unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert!(output_text.starts_with("- lineCode: "));
+    assert!(output_text.contains("  timeBetweenTrains: "));
+}
+
+#[test]
+fn get_near_departures_requires_table() {
+    let mut cmd = get_cmd();
+    let output = cmd
+        .args(vec![
+            "--output",
+            "json",
+            "near",
+            "38.8833",
+            "-77.1043",
+            "--departures",
+        ])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let error_text = String::from_utf8(output.stderr).unwrap();
+    assert!(error_text.contains("only supported with table output"));
 }
 
 #[test]
@@ -38,10 +268,7 @@ fn get_departures_by_code() {
     let mut cmd = get_cmd();
     let output = cmd.args(vec!["departures", "K03"]).output().unwrap();
     let output_text = String::from_utf8(output.stdout).unwrap();
-    assert_eq!(
-        output_text.contains("Departures for Virginia Square-GMU (K03)"),
-        true
-    );
+    assert!(output_text.contains("Departures for Virginia Square-GMU (K03)"));
 }
 
 #[test]
@@ -49,16 +276,132 @@ fn get_departures_by_name() {
     let mut cmd = get_cmd();
     let output = cmd.args(vec!["departures", "GMU"]).output().unwrap();
     let output_text = String::from_utf8(output.stdout).unwrap();
-    assert_eq!(
-        output_text.contains("Departures for Virginia Square-GMU (K03)"),
-        true
-    );
+    assert!(output_text.contains("Departures for Virginia Square-GMU (K03)"));
 }
 
 #[test]
-fn print_stations() {
+fn get_departures_by_fuzzy_name() {
+    let mut cmd = get_cmd();
+    let output = cmd
+        .args(vec!["departures", "virginia sq"])
+        .output()
+        .unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert!(output_text.contains("Departures for Virginia Square-GMU (K03)"));
+}
+
+#[test]
+fn suggest_stations_for_unknown_name() {
     let mut cmd = get_cmd();
+    let output = cmd.args(vec!["departures", "Metro Ctr"]).output().unwrap();
+    let std_err = String::from_utf8(output.stderr).unwrap();
+    assert!(!output.status.success());
+    assert!(std_err.contains("Did you mean Metro Center (C01)"));
+}
+
+#[test]
+fn suggest_stations_for_ambiguous_name() {
+    let mut cmd = get_cmd();
+    let output = cmd.args(vec!["departures", "farragut"]).output().unwrap();
+    let std_err = String::from_utf8(output.stderr).unwrap();
+    assert!(!output.status.success());
+    assert!(std_err.contains("Farragut North (A02)"));
+    assert!(std_err.contains("Farragut West (C03)"));
+}
+
+#[test]
+fn print_nearby_stations() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+    let output = cmd
+        .args(vec!["near", "38.8964", "-77.0716", "-n", "2"])
+        .output()
+        .unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert!(output_text.contains("Rosslyn"));
+    assert!(!output_text.contains("Departures for"));
+}
+
+#[test]
+fn print_nearby_stations_with_departures() {
+    let mut cmd = get_cmd();
+    let output = cmd
+        .args(vec!["near", "38.8833", "-77.1043", "--departures"])
+        .output()
+        .unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert!(output_text.contains("Departures for Virginia Square-GMU (K03)"));
+}
+
+#[test]
+fn print_nearby_complex_with_departures() {
+    // Metro Center's Red line platform is A01, and its other lines stop at C01.
+    let mut cmd = get_cmd();
+    let output = cmd
+        .args(vec![
+            "near",
+            "38.8983",
+            "-77.0281",
+            "-n",
+            "1",
+            "--departures",
+        ])
+        .output()
+        .unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert!(output_text.contains("Departures for Metro Center (A01)"));
+    let blue = output_text.find("Largo").unwrap();
+    let red = output_text.find("Glenmont").unwrap();
+    assert!(blue < red, "{output_text}");
+}
+
+#[test]
+fn print_metrics() {
+    let mut cmd = get_cmd();
+    let output = cmd.args(vec!["metrics"]).output().unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert!(output_text.contains("Metrorail Line Metrics"));
+    assert!(output_text.contains("RD"));
+    assert!(output_text.contains("GR"));
+}
+
+#[test]
+fn print_metrics_for_line() {
+    let mut cmd = get_cmd();
+    let output = cmd
+        .args(vec!["metrics", "--line", "blue"])
+        .output()
+        .unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert!(output_text.contains("BL"));
+    assert!(!output_text.contains("RD"));
+}
+
+#[test]
+fn print_gaps() {
+    let mut cmd = get_cmd();
+    let output = cmd.args(vec!["gaps"]).output().unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    let naylor_road = output_text.find("Naylor Road").unwrap();
+    let capitol_heights = output_text.find("Capitol Heights").unwrap();
+    assert!(naylor_road < capitol_heights, "{output_text}");
+}
+
+#[test]
+fn print_gaps_above_threshold() {
+    let mut cmd = get_cmd();
+    let output = cmd
+        .args(vec!["gaps", "--threshold", "10"])
+        .output()
+        .unwrap();
+    let output_text = String::from_utf8(output.stdout).unwrap();
+    assert!(output_text.contains("Naylor Road"));
+    assert!(!output_text.contains("Capitol Heights"));
+}
+
+#[test]
+fn print_stations() {
+    let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
     let output = cmd.args(vec!["stations"]).output().unwrap();
     let output_text = String::from_utf8(output.stdout).unwrap();
-    assert_eq!(output_text.contains("Rosslyn"), true)
+    assert!(output_text.contains("Rosslyn"))
 }